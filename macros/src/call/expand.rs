@@ -1,5 +1,5 @@
 use super::parse::CallDef;
-use quote::quote;
+use quote::{format_ident, quote};
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
@@ -9,8 +9,8 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
 
 	// This is a nested vector of all the arguments for each of the functions in `fn_name`. It does
-	// not include the `self` or `caller: T::AccountID` parameter, which we always assume are the
-	// first two parameters to these calls.
+	// not include the `self` (or `runtime: &mut T`) or `caller: T::AccountID` parameter, which we
+	// always assume are the first two parameters to these calls.
 	let args_name = methods
 		.iter()
 		.map(|method| method.args.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>())
@@ -23,42 +23,206 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
+	// This is a vector of the function calls for each of the functions in `fn_name`. Functions
+	// taking `self` are called on the pallet stored in the runtime, while functions taking
+	// `runtime: &mut T` are given the whole runtime. Unsigned functions are not given a `caller`.
+	let fn_call = methods
+		.iter()
+		.map(|method| {
+			let name = &method.name;
+			let args = method.args.iter().map(|(name, _)| name);
+			let caller = if method.unsigned {
+				quote! {}
+			} else {
+				quote! { caller, }
+			};
+			if method.with_runtime {
+				quote! { #pallet_struct::<T>::#name(runtime, #caller #( #args ),*) }
+			} else {
+				quote! {
+					<T as crate::support::GetPallet<#pallet_struct<T>>>::pallet_mut(runtime)
+						.#name(#caller #( #args ),*)
+				}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// These are the bodies of the match arms for dispatching each of the functions in `fn_name`,
+	// from a signed and from an unsigned extrinsic respectively. A call dispatched from the wrong
+	// kind of extrinsic fails.
+	let signed_dispatch = methods
+		.iter()
+		.zip(&fn_call)
+		.map(|(method, fn_call)| {
+			if method.unsigned {
+				quote! { Err("Call must be unsigned") }
+			} else {
+				quote! { #fn_call }
+			}
+		})
+		.collect::<Vec<_>>();
+	let unsigned_dispatch = methods
+		.iter()
+		.zip(&fn_call)
+		.map(|(method, fn_call)| {
+			if method.unsigned {
+				quote! { #fn_call }
+			} else {
+				quote! { Err("Call must be signed") }
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// This is a nested vector of the names used to bind the arguments of a second `Call` when
+	// comparing two calls. It follows `args_name`.
+	let other_args_name = args_name
+		.iter()
+		.map(|names| names.iter().map(|name| format_ident!("other_{}", name)).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+
+	// This quote block implements common traits for the `enum Call`. We cannot simply derive
+	// them, since that would require `T` itself to implement them, so instead we require that all
+	// of the argument types do.
+	let traits_impl = quote! {
+		impl<T: Config> Clone for Call<T>
+		where
+			#( #( #args_type: Clone, )* )*
+		{
+			fn clone(&self) -> Self {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							Call::#fn_name { #( #args_name: #args_name.clone() ),* }
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		impl<T: Config> core::fmt::Debug for Call<T>
+		where
+			#( #( #args_type: core::fmt::Debug, )* )*
+		{
+			fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							f.debug_struct(stringify!(#fn_name))
+								#( .field(stringify!(#args_name), #args_name) )*
+								.finish()
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+
+		impl<T: Config> PartialEq for Call<T>
+		where
+			#( #( #args_type: PartialEq, )* )*
+		{
+			fn eq(&self, other: &Self) -> bool {
+				// With a single variant, the last arm is unreachable.
+				#[allow(unreachable_patterns)]
+				match (self, other) {
+					#(
+						(
+							Call::#fn_name { #( #args_name ),* },
+							Call::#fn_name { #( #args_name: #other_args_name ),* },
+						) => true #( && #args_name == #other_args_name )*,
+					)*
+					_ => false,
+				}
+			}
+		}
+
+		impl<T: Config> Eq for Call<T> where #( #( #args_type: Eq, )* )* {}
+
+		impl<T: Config> core::hash::Hash for Call<T>
+		where
+			#( #( #args_type: core::hash::Hash, )* )*
+		{
+			fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+				core::mem::discriminant(self).hash(state);
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							#( #args_name.hash(state); )*
+						},
+					)*
+					Call::__Ignore(_, never) => match *never {},
+				}
+			}
+		}
+	};
+
 	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
-	// and the `Dispatch` trait logic to route a `caller` to access those functions.
+	// and the dispatch logic to route a `caller` to access those functions.
 	let dispatch_impl = quote! {
 		// The callable functions exposed by this pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
+		//
+		// The `__Ignore` variant can never be constructed. It only uses `T`, so that pallets
+		// without any calls still have a valid `Call` enum.
 		#[allow(non_camel_case_types)]
 		pub enum Call<T: Config> {
 			#(
 				#fn_name { #( #args_name: #args_type),* },
 			)*
+			#[doc(hidden)]
+			__Ignore(core::marker::PhantomData<T>, core::convert::Infallible),
 		}
 
 		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
 		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountID;
-			type Call = Call<T>;
+		//
+		// Calls are dispatched with access to the whole runtime `T`, so that pallets can depend
+		// on the state of other pallets.
+		impl<T: Config> Call<T> {
+			// Not every pallet has both signed and unsigned calls.
+			#[allow(unused_variables)]
+			pub fn dispatch(
+				self,
+				runtime: &mut T,
+				caller: T::AccountID,
+			) -> crate::support::DispatchResult
+			where
+				T: crate::support::GetPallet<#pallet_struct<T>>,
+			{
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							// Note that we assume the first argument of every call is the `caller`.
+							#signed_dispatch
+						},
+					)*
+					Call::__Ignore(_, never) => match never {},
+				}
+			}
 
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
-				match call {
+			#[allow(unused_variables)]
+			pub fn dispatch_unsigned(self, runtime: &mut T) -> crate::support::DispatchResult
+			where
+				T: crate::support::GetPallet<#pallet_struct<T>>,
+			{
+				match self {
 					#(
 						Call::#fn_name { #( #args_name ),* } => {
-							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
-								#( #args_name ),*
-							)?;
+							#unsigned_dispatch
 						},
 					)*
+					Call::__Ignore(_, never) => match never {},
 				}
-				Ok(())
 			}
 		}
 	};
 
 	// Return the generated code.
-	dispatch_impl.into()
+	quote! {
+		#dispatch_impl
+		#traits_impl
+	}
+	.into()
 }
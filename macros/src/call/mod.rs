@@ -6,11 +6,16 @@ pub fn call(
 	_attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+	let item_mod = syn::parse_macro_input!(item as syn::Item);
+
 	// The final expanded code will be placed here.
 	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
-	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	// except for the `#[unsigned]` markers which only have a meaning for this macro. Calls taking a
+	// `RuntimeCall` must box it, since `RuntimeCall` contains the `Call` of every pallet, so we
+	// allow clippy's `boxed_local` lint on them.
+	let item_impl = parse::strip_unsigned_attrs(item_mod.clone());
+	let mut finished: proc_macro::TokenStream =
+		quote::quote! { #[allow(clippy::boxed_local)] #item_impl }.into();
 
 	// First we parse the call functions implemented for the pallet...
 	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(item_mod.clone()) {
@@ -25,6 +25,12 @@ pub struct CallVariantDef {
 	pub name: syn::Ident,
 	/// Information on args of the function: `(name, type)`.
 	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	/// Whether the function takes `runtime: &mut T` instead of a variant of `self`, which gives it
+	/// access to the state of every pallet in the runtime.
+	pub with_runtime: bool,
+	/// Whether the function is marked `#[unsigned]`, in which case it takes no `caller`, and can
+	/// only be dispatched from an unsigned extrinsic.
+	pub unsigned: bool,
 }
 
 impl CallDef {
@@ -50,32 +56,46 @@ impl CallDef {
 				// Here is where we will store all the args for each callable functions.
 				let mut args = vec![];
 
-				// First argument should be some variant of `self`.
-				match method.sig.inputs.first() {
-					Some(syn::FnArg::Receiver(_)) => {},
-					_ => {
-						let msg = "Invalid call, first argument must be a variant of self";
-						return Err(syn::Error::new(method.sig.span(), msg))
-					},
-				}
-
-				// The second argument should be the `caller: T::AccountID` argument.
-				match method.sig.inputs.iter().skip(1).next() {
+				// First argument should be some variant of `self`, or `runtime: &mut T`.
+				let with_runtime = match method.sig.inputs.first() {
+					Some(syn::FnArg::Receiver(_)) => false,
 					Some(syn::FnArg::Typed(arg)) => {
 						// Here we specifically check that this argument is as we expect for
-						// `caller: T::AccountID`.
-						check_caller_arg(arg)?;
+						// `runtime: &mut T`.
+						check_runtime_arg(arg)?;
+						true
 					},
 					_ => {
-						let msg = "Invalid call, second argument should be `caller: T::AccountID`";
+						let msg = "Invalid call, first argument must be self or `runtime: &mut T`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
+				};
+
+				// Unsigned calls have no `caller`, so all their other arguments are call arguments.
+				let unsigned = method.attrs.iter().any(is_unsigned_attr);
+
+				// Otherwise, the second argument should be the `caller: T::AccountID` argument.
+				if !unsigned {
+					match method.sig.inputs.iter().nth(1) {
+						Some(syn::FnArg::Typed(arg)) => {
+							// Here we specifically check that this argument is as we expect for
+							// `caller: T::AccountID`.
+							check_caller_arg(arg)?;
+						},
+						_ => {
+							let msg =
+								"Invalid call, second argument should be `caller: T::AccountID`";
+							return Err(syn::Error::new(method.sig.span(), msg))
+						},
+					}
 				}
 
 				let fn_name = method.sig.ident.clone();
 
-				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
-				for arg in method.sig.inputs.iter().skip(2) {
+				// Parsing the rest of the args. Skipping 2 for `self` (or `runtime`) and `caller`,
+				// or only 1 for unsigned calls.
+				let skip = if unsigned { 1 } else { 2 };
+				for arg in method.sig.inputs.iter().skip(skip) {
 					// All arguments should be typed.
 					let arg = if let syn::FnArg::Typed(arg) = arg {
 						arg
@@ -96,7 +116,7 @@ impl CallDef {
 				}
 
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				methods.push(CallVariantDef { name: fn_name, args, with_runtime, unsigned });
 			}
 		}
 
@@ -105,6 +125,24 @@ impl CallDef {
 	}
 }
 
+/// Check if the attribute is the `#[unsigned]` marker of an unsigned call.
+pub fn is_unsigned_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("unsigned")
+}
+
+/// Remove the `#[unsigned]` markers from the callable functions, since they are not real attributes
+/// and would fail to compile.
+pub fn strip_unsigned_attrs(mut item: syn::Item) -> syn::Item {
+	if let syn::Item::Impl(item_impl) = &mut item {
+		for item in item_impl.items.iter_mut() {
+			if let syn::ImplItem::Fn(method) = item {
+				method.attrs.retain(|attr| !is_unsigned_attr(attr));
+			}
+		}
+	}
+	item
+}
+
 /// Check caller arg is exactly: `caller: T::AccountID`.
 ///
 /// This is kept strict to keep the code simple.
@@ -139,3 +177,37 @@ pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
 
 	Ok(())
 }
+
+/// Check runtime arg is exactly: `runtime: &mut T`.
+///
+/// This is kept strict to keep the code simple.
+pub fn check_runtime_arg(arg: &syn::PatType) -> syn::Result<()> {
+	pub struct CheckDispatchableRuntimeArg;
+	impl syn::parse::Parse for CheckDispatchableRuntimeArg {
+		fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+			input.parse::<syn::Token![&]>()?;
+			input.parse::<syn::Token![mut]>()?;
+			input.parse::<keyword::T>()?;
+			Ok(Self)
+		}
+	}
+
+	// This checks the arg name is `runtime`.
+	if let syn::Pat::Ident(ident) = &*arg.pat {
+		if &ident.ident != "runtime" {
+			let msg = "Invalid name for first parameter: expected `runtime: &mut T`";
+			return Err(syn::Error::new(ident.span(), msg))
+		}
+	}
+
+	// This checks the type is `&mut T` with `CheckDispatchableRuntimeArg`
+	let ty = &arg.ty;
+	syn::parse2::<CheckDispatchableRuntimeArg>(ty.to_token_stream()).map_err(|e| {
+		let msg = "Invalid type for first parameter: expected `runtime: &mut T`";
+		let mut err = syn::Error::new(ty.span(), msg);
+		err.combine(e);
+		err
+	})?;
+
+	Ok(())
+}
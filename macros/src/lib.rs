@@ -1,6 +1,23 @@
 mod call;
 mod runtime;
 
+/// Expand the callable functions of a pallet.
+///
+/// Every function in the `impl` block must take either a variant of `self` or `runtime: &mut T` as
+/// its first argument, followed by `caller: T::AccountID`. Functions taking `runtime: &mut T` can
+/// access the state of other pallets through `support::GetPallet`.
+///
+/// Functions marked `#[unsigned]` take no `caller`, and can only be dispatched from an unsigned
+/// extrinsic, like an inherent. All other functions can only be dispatched from a signed extrinsic.
+///
+/// The `impl` block can be empty, for pallets which are part of the runtime but expose no calls.
+///
+/// This generates:
+/// - `enum Call` - an enum with a variant for each callable function, containing its arguments. It
+///   implements `Clone`, `Debug`, `PartialEq`, `Eq` and `Hash` when all the argument types do.
+/// - `fn Call::dispatch()` - which routes a `Call` and its `caller` to the appropriate function,
+///   using the runtime to access the pallet.
+/// - `fn Call::dispatch_unsigned()` - which does the same for a `Call` without a `caller`.
 #[proc_macro_attribute]
 pub fn call(
 	attr: proc_macro::TokenStream,
@@ -15,16 +32,27 @@ pub fn call(
 /// - `fn new()` - which generates a new instance of the runtime, by instantiating all the pallets
 ///   included in the runtime.
 /// - `fn execute_block()` - which handles basic logic for executing a block of extrinsics. It does
-///   basic actions like incrementing the block number and checking the block to be executed has a
-///   valid block number.
+///   basic actions like incrementing the block number, checking the block to be executed has a
+///   valid block number, and recording the author of the block in the system pallet. It also calls
+///   the `support::Hooks` of every pallet before and after the extrinsics, so every pallet in the
+///   runtime must implement that trait. A hook which fails makes the block invalid. Extrinsics
+///   without a caller are dispatched as unsigned extrinsics. Once the block is executed, its hash
+///   is recorded in the system pallet as the parent hash of the next block. An invalid block is
+///   rejected as a whole: the runtime is restored to its state from before the block, using
+///   `support::with_transaction`.
+/// - `fn apply_block()` - which applies the changes of a block for `fn execute_block()`, without
+///   reverting them if the block is invalid.
 ///
 /// This also generates code needed for dispatching calls to the pallets:
 /// - Note: For simplicity, we assume that the system pallet is not callable.
 /// - `enum RuntimeCall` - an "outer"-enum representing the accumulation of all possible calls to
-///   all pallets. The system pallet is not included.
+///   all pallets. The system pallet is not included. Like the pallet level calls, it can be cloned,
+///   compared and hashed, so that pallets can take it as an argument and store it.
 /// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet. Basic
 ///   logic like incrementing the nonce of the user is included in the generated code. The system
 ///   pallet is not included.
+/// - implements the trait `support::GetPallet` for every pallet, including system, so that pallets
+///   can access each other's state.
 #[proc_macro_attribute]
 pub fn runtime(
 	attr: proc_macro::TokenStream,
@@ -3,7 +3,7 @@ use quote::quote;
 
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { runtime_struct, system, pallets } = def;
 
 	// This is a vector of all the pallet names, not including system.
 	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
@@ -24,21 +24,58 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				}
 			}
 
-			// Execute a block of extrinsics. Increments the block number.
+			// Execute a block of extrinsics. Increments the block number, clears the events of the
+			// previous block, records the author of the block, and runs the hooks of every pallet
+			// around the extrinsics. Once executed, the block becomes the parent of the next one.
+			//
+			// An invalid block, including one for which a hook fails, is rejected as a whole: all
+			// of its changes are reverted.
 			fn execute_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
+				crate::support::with_transaction(self, |runtime| runtime.apply_block(block))
+			}
+
+			// Apply the changes of a block, without reverting them if the block is invalid.
+			fn apply_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
+				let block_hash = crate::support::hash_of(&(self.system.parent_hash(), &block));
 				self.system.inc_block_number();
+				self.system.reset_events();
 				if block.header.block_number != self.system.block_number() {
 					return Err(&"block number does not match what is expected")
 				}
+				self.system.set_block_author(block.header.author.clone());
+				// Run the `on_initialize` hook of every pallet before executing the extrinsics.
+				#(
+					<#pallet_types as crate::support::Hooks<Self, _>>::on_initialize(
+						self,
+						block.header.block_number,
+					)?;
+				)*
 				for (i, support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
-					self.system.inc_nonce(&caller);
-					let _res = self.dispatch(caller, call).map_err(|e| {
+					self.system.set_extrinsic_index(Some(i as u32));
+					// Only signed extrinsics increment the nonce of their caller.
+					let result = match caller {
+						Some(caller) => {
+							self.system.inc_nonce(&caller);
+							self.dispatch(caller, call)
+						},
+						None => self.dispatch_unsigned(call),
+					};
+					let _res = result.map_err(|e| {
 						eprintln!(
 							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
 							block.header.block_number, i, e
 						)
 					});
 				}
+				self.system.set_extrinsic_index(None);
+				// Run the `on_finalize` hook of every pallet after executing the extrinsics.
+				#(
+					<#pallet_types as crate::support::Hooks<Self, _>>::on_finalize(
+						self,
+						block.header.block_number,
+					)?;
+				)*
+				self.system.set_parent_hash(block_hash);
 				Ok(())
 			}
 		}
@@ -51,6 +88,7 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
+		#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 		pub enum RuntimeCall {
 			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
 		}
@@ -73,7 +111,23 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				match runtime_call {
 					#(
 						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
+							call.dispatch(self, caller)?;
+						}
+					),*
+				}
+				Ok(())
+			}
+
+			// Dispatch a call from an unsigned extrinsic, routing it to the appropriate pallet
+			// level call in the same way as `dispatch`.
+			fn dispatch_unsigned(
+				&mut self,
+				runtime_call: Self::Call,
+			) -> crate::support::DispatchResult {
+				match runtime_call {
+					#(
+						RuntimeCall::#pallet_names(call) => {
+							call.dispatch_unsigned(self)?;
 						}
 					),*
 				}
@@ -82,10 +136,37 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 		}
 	};
 
+	// This quote block gives pallets access to each other through the `Runtime` struct, by
+	// implementing `GetPallet` for every pallet, including system.
+	let get_pallet_impl = quote! {
+		impl crate::support::GetPallet<#system> for #runtime_struct {
+			fn pallet(&self) -> &#system {
+				&self.system
+			}
+
+			fn pallet_mut(&mut self) -> &mut #system {
+				&mut self.system
+			}
+		}
+
+		#(
+			impl crate::support::GetPallet<#pallet_types> for #runtime_struct {
+				fn pallet(&self) -> &#pallet_types {
+					&self.#pallet_names
+				}
+
+				fn pallet_mut(&mut self) -> &mut #pallet_types {
+					&mut self.#pallet_names
+				}
+			}
+		)*
+	};
+
 	// We combine and return all the generated code.
 	quote! {
 		#dispatch_impl
 		#runtime_impl
+		#get_pallet_impl
 	}
 	.into()
 }
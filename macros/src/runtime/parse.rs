@@ -5,6 +5,8 @@ use syn::spanned::Spanned;
 pub struct RuntimeDef {
 	/// This is the name of the struct used by the user. We mostly assume it is `Runtime`.
 	pub runtime_struct: syn::Ident,
+	/// This is the type of the `system` pallet, which we check is the first field of the struct.
+	pub system: syn::Type,
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
 	pub pallets: Vec<(syn::Ident, syn::Type)>,
@@ -23,6 +25,7 @@ impl RuntimeDef {
 		check_system(&item_struct)?;
 
 		let runtime_struct = item_struct.ident;
+		let system = item_struct.fields.iter().next().map(|field| field.ty.clone()).unwrap();
 
 		// Here is where we will store a list of all the pallets.
 		let mut pallets = vec![];
@@ -33,7 +36,7 @@ impl RuntimeDef {
 			}
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		Ok(Self { runtime_struct, system, pallets })
 	}
 }
 
@@ -0,0 +1,193 @@
+use crate::{
+	support::{self, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+
+pub trait Config:
+	system::Config<BlockNumber: Into<u64>, RuntimeEvent: From<Event<Self::AccountID>>>
+	+ Sized
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can change the authorities.
+	type AuthorityOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The maximum number of authorities.
+	const MAX_AUTHORITIES: usize;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// The authorities were changed, starting from the next block.
+	AuthoritiesChanged { authorities: Vec<AccountID> },
+}
+
+/// This is the Aura Module.
+/// It implements Proof-of-Authority block authorship: a set of authorities take turns authoring
+/// blocks, with block `n` authored by the authority at index `n % authorities.len()`. The author
+/// in the header of every block is checked against the expected authority.
+///
+/// Until authorities are set, any account can author blocks, which is convenient for development
+/// chains.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The authorities, in the order in which they author blocks.
+	authorities: Vec<T::AccountID>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Aura Module.
+	pub fn new() -> Self {
+		Self { authorities: Vec::new() }
+	}
+
+	/// Get the authorities.
+	pub fn authorities(&self) -> &[T::AccountID] {
+		&self.authorities
+	}
+
+	/// Get the authority expected to author block `n`, or `None` if there are no authorities.
+	pub fn expected_author(&self, n: T::BlockNumber) -> Option<&T::AccountID> {
+		if self.authorities.is_empty() {
+			return None;
+		}
+		let slot = n.into() % self.authorities.len() as u64;
+		self.authorities.get(slot as usize)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Make sure that block `n` was authored by the expected authority.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		let Some(expected) = GetPallet::<Pallet<T>>::pallet(runtime).expected_author(n) else {
+			return Ok(());
+		};
+		if GetPallet::<system::Pallet<T>>::pallet(runtime).block_author() != Some(expected) {
+			return Err("Unexpected block author");
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Replace the authorities with `authorities`, which take turns in the given order from the
+	/// next block.
+	/// Only the `AuthorityOrigin` can change the authorities.
+	pub fn set_authorities(
+		runtime: &mut T,
+		caller: T::AccountID,
+		authorities: Vec<T::AccountID>,
+	) -> DispatchResult {
+		T::AuthorityOrigin::ensure_origin(runtime, &caller)?;
+		if authorities.is_empty() {
+			return Err("No authorities");
+		}
+		if authorities.len() > T::MAX_AUTHORITIES {
+			return Err("Too many authorities");
+		}
+		let mut sorted = authorities.clone();
+		sorted.sort();
+		sorted.dedup();
+		if sorted.len() != authorities.len() {
+			return Err("Duplicate authority");
+		}
+
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).authorities = authorities.clone();
+		Self::deposit_event(runtime, Event::AuthoritiesChanged { authorities });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{new_test_runtime, run_to_block, support, timestamp, types, Runtime, RuntimeCall};
+
+	type Aura = super::Pallet<Runtime>;
+
+	fn block_by(author: &str, block_number: types::BlockNumber) -> types::Block {
+		let now = block_number as types::Moment * 6_000;
+		types::Block {
+			header: types::Header { block_number, author: author.to_string() },
+			extrinsics: vec![types::Extrinsic {
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now }),
+			}],
+		}
+	}
+
+	#[test]
+	fn authorities_take_turns() {
+		let mut runtime = new_test_runtime(&[]);
+		let authorities = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Aura::set_authorities(&mut runtime, support::root_account(), authorities);
+		assert_eq!(result, Ok(()));
+		assert_eq!(runtime.aura.expected_author(1), Some(&"bob".to_string()));
+		assert_eq!(runtime.aura.expected_author(3), Some(&"alice".to_string()));
+
+		assert_eq!(runtime.execute_block(block_by("bob", 1)), Ok(()));
+		assert_eq!(runtime.system.block_author(), Some(&"bob".to_string()));
+		assert_eq!(runtime.execute_block(block_by("charlie", 2)), Ok(()));
+		assert_eq!(runtime.execute_block(block_by("bob", 3)), Err("Unexpected block author"));
+
+		// The rejected block does not change the state.
+		assert_eq!(runtime.system.block_number(), 2);
+		assert_eq!(runtime.system.block_author(), Some(&"charlie".to_string()));
+		assert_eq!(runtime.execute_block(block_by("alice", 3)), Ok(()));
+	}
+
+	#[test]
+	fn set_authorities() {
+		let mut runtime = new_test_runtime(&[]);
+		let authorities = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Aura::set_authorities(&mut runtime, support::root_account(), authorities);
+		assert_eq!(result, Ok(()));
+		let root = support::root_account::<String>();
+
+		assert_eq!(
+			Aura::set_authorities(
+				&mut runtime,
+				"alice".into(),
+				["alice"].map(String::from).to_vec()
+			),
+			Err("Bad origin")
+		);
+		assert_eq!(
+			Aura::set_authorities(&mut runtime, root.clone(), Vec::new()),
+			Err("No authorities")
+		);
+		assert_eq!(
+			Aura::set_authorities(
+				&mut runtime,
+				root.clone(),
+				["dave", "eve", "dave"].map(String::from).to_vec()
+			),
+			Err("Duplicate authority")
+		);
+		assert_eq!(
+			Aura::set_authorities(
+				&mut runtime,
+				root.clone(),
+				["eve", "dave"].map(String::from).to_vec()
+			),
+			Ok(())
+		);
+
+		// The new authorities author blocks from the next one, in the given order.
+		run_to_block(&mut runtime, 2);
+		assert_eq!(runtime.system.block_author(), Some(&"eve".to_string()));
+		assert_eq!(runtime.execute_block(block_by("alice", 3)), Err("Unexpected block author"));
+	}
+
+	#[test]
+	fn anyone_authors_without_authorities() {
+		let mut runtime = Runtime::new();
+		assert_eq!(runtime.aura.expected_author(1), None);
+		assert_eq!(runtime.execute_block(block_by("dave", 1)), Ok(()));
+		assert_eq!(runtime.system.block_author(), Some(&"dave".to_string()));
+	}
+}
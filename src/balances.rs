@@ -1,25 +1,64 @@
+use core::fmt::Debug;
 use num::traits::{CheckedAdd, CheckedSub, Zero};
 use std::collections::BTreeMap;
 
-use crate::support;
+use crate::support::{self, Lookup};
 
-pub trait Config: crate::system::Config {
-	type Balance: Zero + CheckedAdd + CheckedSub + Copy;
+pub trait Config: crate::system::Config + Sized {
+	type Balance: Zero + CheckedAdd + CheckedSub + Copy + Ord + Debug;
+	/// The handler for funds which are taken from an account without being given to another, like
+	/// fees and slashed deposits.
+	type OnUnbalanced: OnUnbalanced<Self>;
+	/// Looks up the recipient of a transfer, which may refer to an account with something other
+	/// than its `AccountID`.
+	type Lookup: support::Lookup<Self, Target = Self::AccountID>;
 }
 
+/// The type used by a runtime `T` to refer to an account in calls.
+pub type AccountIdLookupOf<T> = <<T as Config>::Lookup as support::Lookup<T>>::Source;
+
+/// A handler for funds which were removed from an account without being given to another account.
+pub trait OnUnbalanced<T: Config> {
+	/// Handle `amount` of funds which were removed from some account.
+	fn on_unbalanced(balances: &mut Pallet<T>, amount: T::Balance);
+}
+
+/// Simply destroy the funds.
+impl<T: Config> OnUnbalanced<T> for () {
+	fn on_unbalanced(_balances: &mut Pallet<T>, _amount: T::Balance) {}
+}
+
+/// Whether funds moved to an account go to its balance or its reserved balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceStatus {
+	/// The funds go to the balance, and can be used right away.
+	Free,
+	/// The funds go to the reserved balance.
+	Reserved,
+}
+
+/// An identifier for a lock on an account's balance. Each pallet placing locks uses its own
+/// identifier, so that it can update or remove its lock without affecting the others.
+pub type LockIdentifier = &'static str;
+
 /// This is the Balances Module.
 /// It is a simple module which keeps track of how much balance each account has in this state
 /// machine.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Pallet<T: Config> {
 	// A simple storage mapping from accounts (`String`) to their balances (`u128`).
 	balances: BTreeMap<T::AccountID, T::Balance>,
+	/// A map from an account to the locks on its balance. Locked funds cannot be transferred away.
+	locks: BTreeMap<T::AccountID, BTreeMap<LockIdentifier, T::Balance>>,
+	/// A map from an account to its reserved balance, like a deposit. Reserved funds are not part
+	/// of the account's balance until they are unreserved.
+	reserves: BTreeMap<T::AccountID, T::Balance>,
 }
 
 impl<T: Config> Pallet<T> {
 	/// Create a new instance of the balances module.
 	pub fn new() -> Self {
-		Self { balances: BTreeMap::new() }
+		Self { balances: BTreeMap::new(), locks: BTreeMap::new(), reserves: BTreeMap::new() }
 	}
 
 	/// Set the balance of an account `who` to some `amount`.
@@ -32,15 +71,135 @@ impl<T: Config> Pallet<T> {
 	pub fn balance(&self, who: &T::AccountID) -> T::Balance {
 		*self.balances.get(who).unwrap_or(&T::Balance::zero())
 	}
-}
 
+	/// Lock `amount` of the balance of `who` under the identifier `id`, replacing any existing lock
+	/// with the same identifier. Setting a lock of zero removes it.
+	pub fn set_lock(&mut self, id: LockIdentifier, who: &T::AccountID, amount: T::Balance) {
+		if amount.is_zero() {
+			return self.remove_lock(id, who);
+		}
+		self.locks.entry(who.clone()).or_default().insert(id, amount);
+	}
 
-#[macros::call]
-impl<T: Config> Pallet<T> {
-	/// Transfer `amount` from one account to another.
-	/// This function verifies that `from` has at least `amount` balance to transfer,
-	/// and that no mathematical overflows occur.
-	pub fn transfer(
+	/// Remove the lock with identifier `id` from the balance of `who`.
+	pub fn remove_lock(&mut self, id: LockIdentifier, who: &T::AccountID) {
+		if let Some(locks) = self.locks.get_mut(who) {
+			locks.remove(id);
+			if locks.is_empty() {
+				self.locks.remove(who);
+			}
+		}
+	}
+
+	/// Get the amount of the balance of `who` which is locked.
+	/// Locks overlap rather than stack, so this is the largest lock on the account.
+	pub fn locked(&self, who: &T::AccountID) -> T::Balance {
+		self.locks
+			.get(who)
+			.and_then(|locks| locks.values().max().copied())
+			.unwrap_or(T::Balance::zero())
+	}
+
+	/// Get the balance of `who` which is not locked, and can be transferred.
+	pub fn usable_balance(&self, who: &T::AccountID) -> T::Balance {
+		self.balance(who).checked_sub(&self.locked(who)).unwrap_or(T::Balance::zero())
+	}
+
+	/// Get the reserved balance of `who`.
+	pub fn reserved_balance(&self, who: &T::AccountID) -> T::Balance {
+		*self.reserves.get(who).unwrap_or(&T::Balance::zero())
+	}
+
+	/// Add `amount` to the balance of `who`, creating those funds.
+	pub fn deposit(&mut self, who: &T::AccountID, amount: T::Balance) -> support::DispatchResult {
+		let new_balance = self.balance(who).checked_add(&amount).ok_or("Balance overflow error")?;
+		self.set_balance(who, new_balance);
+		Ok(())
+	}
+
+	/// Move `amount` of the balance of `who` into their reserved balance.
+	/// This function will return an error if the funds are not available, or locked.
+	pub fn reserve(&mut self, who: &T::AccountID, amount: T::Balance) -> support::DispatchResult {
+		if amount > self.usable_balance(who) {
+			return Err("Not enough funds");
+		}
+		let new_reserved = self
+			.reserved_balance(who)
+			.checked_add(&amount)
+			.ok_or("Balance overflow error")?;
+		let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds")?;
+		self.set_balance(who, new_balance);
+		self.reserves.insert(who.clone(), new_reserved);
+		Ok(())
+	}
+
+	/// Move up to `amount` of the reserved balance of `who` back into their balance.
+	/// Returns the amount which was actually unreserved.
+	pub fn unreserve(&mut self, who: &T::AccountID, amount: T::Balance) -> T::Balance {
+		let amount = self.take_reserved(who, amount);
+		// If the balance of `who` would overflow, the funds are handled as unbalanced instead.
+		if self.deposit(who, amount).is_err() {
+			T::OnUnbalanced::on_unbalanced(self, amount);
+		}
+		amount
+	}
+
+	/// Remove up to `amount` of the reserved balance of `who`, and hand it to `T::OnUnbalanced`.
+	/// Returns the amount which was actually slashed.
+	pub fn slash_reserved(&mut self, who: &T::AccountID, amount: T::Balance) -> T::Balance {
+		let amount = self.take_reserved(who, amount);
+		T::OnUnbalanced::on_unbalanced(self, amount);
+		amount
+	}
+
+	/// Move up to `amount` of the reserved balance of `from` into the balance or the reserved
+	/// balance of `to`, depending on `status`. Returns the amount which was actually moved.
+	pub fn repatriate_reserved(
+		&mut self,
+		from: &T::AccountID,
+		to: &T::AccountID,
+		amount: T::Balance,
+		status: BalanceStatus,
+	) -> T::Balance {
+		let amount = self.take_reserved(from, amount);
+		let result = match status {
+			BalanceStatus::Free => self.deposit(to, amount),
+			BalanceStatus::Reserved => self.deposit_reserved(to, amount),
+		};
+		// If the balance of `to` would overflow, the funds are handled as unbalanced instead.
+		if result.is_err() {
+			T::OnUnbalanced::on_unbalanced(self, amount);
+		}
+		amount
+	}
+
+	/// Remove up to `amount` from the balance of `who`, even if it is locked, and hand it to
+	/// `T::OnUnbalanced`. Returns the amount which was actually slashed.
+	pub fn slash(&mut self, who: &T::AccountID, amount: T::Balance) -> T::Balance {
+		let balance = self.balance(who);
+		let amount = amount.min(balance);
+		self.set_balance(who, balance.checked_sub(&amount).unwrap_or(T::Balance::zero()));
+		T::OnUnbalanced::on_unbalanced(self, amount);
+		amount
+	}
+
+	/// Remove `amount` from the balance of `who`, and hand it to `T::OnUnbalanced`. This is used
+	/// to charge fees.
+	/// This function will return an error if the funds are not available, or locked.
+	pub fn burn(&mut self, who: &T::AccountID, amount: T::Balance) -> support::DispatchResult {
+		if amount > self.usable_balance(who) {
+			return Err("Not enough funds");
+		}
+		let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds")?;
+		self.set_balance(who, new_balance);
+		T::OnUnbalanced::on_unbalanced(self, amount);
+		Ok(())
+	}
+
+	/// Transfer `amount` from `caller` to `to`.
+	/// This function verifies that `caller` has at least `amount` balance to transfer without
+	/// touching its locked funds, and that no mathematical overflows occur.
+	pub fn do_transfer(
 		&mut self,
 		caller: T::AccountID,
 		to: T::AccountID,
@@ -54,11 +213,62 @@ impl<T: Config> Pallet<T> {
 		let final_balance_to =
 			init_balance_to.checked_add(&amount).ok_or("Balance overflow error")?;
 
+		if final_balance_caller < self.locked(&caller) {
+			return Err("Balance is locked");
+		}
+
 		self.set_balance(&caller, final_balance_caller);
 		self.set_balance(&to, final_balance_to);
 
 		Ok(())
 	}
+
+	/// Add `amount` to the reserved balance of `who`.
+	fn deposit_reserved(
+		&mut self,
+		who: &T::AccountID,
+		amount: T::Balance,
+	) -> support::DispatchResult {
+		let new_reserved = self
+			.reserved_balance(who)
+			.checked_add(&amount)
+			.ok_or("Balance overflow error")?;
+		self.reserves.insert(who.clone(), new_reserved);
+		Ok(())
+	}
+
+	/// Remove up to `amount` from the reserved balance of `who`, and return the amount removed.
+	fn take_reserved(&mut self, who: &T::AccountID, amount: T::Balance) -> T::Balance {
+		let reserved = self.reserved_balance(who);
+		let amount = amount.min(reserved);
+		let remaining = reserved.checked_sub(&amount).unwrap_or(T::Balance::zero());
+		if remaining.is_zero() {
+			self.reserves.remove(who);
+		} else {
+			self.reserves.insert(who.clone(), remaining);
+		}
+		amount
+	}
+}
+
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Transfer `amount` from the `caller` to the account `to` refers to.
+	pub fn transfer(
+		runtime: &mut T,
+		caller: T::AccountID,
+		to: AccountIdLookupOf<T>,
+		amount: T::Balance,
+	) -> support::DispatchResult
+	where
+		T: support::GetPallet<Pallet<T>>,
+	{
+		let to = T::Lookup::lookup(runtime, to)?;
+		support::GetPallet::<Pallet<T>>::pallet_mut(runtime).do_transfer(caller, to, amount)
+	}
 }
 
 
@@ -70,10 +280,13 @@ mod test {
 		type AccountID = String;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type RuntimeEvent = ();
 	}
 
 	impl super::Config for TestConfig {
 		type Balance = u128;
+		type OnUnbalanced = ();
+		type Lookup = crate::support::IdentityLookup<String>;
 	}
 
 	#[test]
@@ -90,12 +303,58 @@ mod test {
 	fn transfer_balance() {
 		let mut balances = super::Pallet::<TestConfig>::new();
 
-		assert!(balances.transfer("alice".to_string(), "bob".to_string(), 100).is_err());
+		assert!(balances.do_transfer("alice".to_string(), "bob".to_string(), 100).is_err());
 
 		balances.set_balance(&"alice".to_string(), 100);
-		assert!(balances.transfer("alice".to_string(), "bob".to_string(), 50).is_ok());
+		assert!(balances.do_transfer("alice".to_string(), "bob".to_string(), 50).is_ok());
 
 		assert_eq!(balances.balance(&"alice".to_string()), 50);
 		assert_eq!(balances.balance(&"bob".to_string()), 50);
 	}
+
+	#[test]
+	fn locked_balance() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		balances.set_balance(&alice, 100);
+		balances.set_lock("vesting", &alice, 60);
+		balances.set_lock("staking", &alice, 30);
+		assert_eq!(balances.locked(&alice), 60);
+		assert_eq!(balances.usable_balance(&alice), 40);
+
+		assert_eq!(balances.do_transfer(alice.clone(), bob.clone(), 50), Err("Balance is locked"));
+		assert!(balances.do_transfer(alice.clone(), bob.clone(), 40).is_ok());
+
+		balances.remove_lock("vesting", &alice);
+		assert_eq!(balances.locked(&alice), 30);
+		balances.set_lock("staking", &alice, 0);
+		assert_eq!(balances.locked(&alice), 0);
+		assert!(balances.do_transfer(alice.clone(), bob, 60).is_ok());
+	}
+
+	#[test]
+	fn reserved_balance() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		balances.set_balance(&alice, 100);
+		balances.set_lock("staking", &alice, 50);
+		assert_eq!(balances.reserve(&alice, 60), Err("Not enough funds"));
+		assert_eq!(balances.reserve(&alice, 30), Ok(()));
+		assert_eq!(balances.balance(&alice), 70);
+		assert_eq!(balances.reserved_balance(&alice), 30);
+
+		assert_eq!(balances.unreserve(&alice, 10), 10);
+		assert_eq!(balances.balance(&alice), 80);
+		// Only the remaining reserved balance is slashed, and the slashed funds are destroyed.
+		assert_eq!(balances.slash_reserved(&alice, 50), 20);
+		assert_eq!(balances.reserved_balance(&alice), 0);
+		assert_eq!(balances.balance(&alice), 80);
+
+		assert_eq!(balances.burn(&alice, 40), Err("Not enough funds"));
+		assert_eq!(balances.burn(&alice, 30), Ok(()));
+		assert_eq!(balances.balance(&alice), 50);
+	}
 }
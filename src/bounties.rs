@@ -0,0 +1,415 @@
+use crate::{
+	balances,
+	support::{self, DeriveAccount, DispatchResult, EnsureOrigin, GetPallet},
+	system, treasury,
+};
+use num::traits::{CheckedAdd, CheckedSub};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	treasury::Config<RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>>
+	+ GetPallet<treasury::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can approve and close bounties, and propose their curators.
+	type ApproveOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The deposit reserved from the proposer of a bounty. It is returned once the bounty is
+	/// approved, and slashed if it is closed before.
+	const BOUNTY_DEPOSIT: Self::Balance;
+	/// The deposit reserved from a curator while it curates a bounty.
+	const CURATOR_DEPOSIT: Self::Balance;
+	/// The number of blocks after a bounty is awarded before its beneficiary can claim it.
+	const PAYOUT_DELAY: Self::BlockNumber;
+	/// The maximum length of the description of a bounty.
+	const MAX_DESCRIPTION_LENGTH: usize;
+}
+
+/// The index of a bounty.
+pub type BountyIndex = u32;
+
+/// The status of a bounty. A bounty moves through these in order, unless it is closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BountyStatus<AccountID, Balance, BlockNumber> {
+	/// The bounty is waiting to be approved.
+	Proposed,
+	/// The bounty was approved, and its value moved from the treasury to the bounty account. It is
+	/// waiting for a curator.
+	Funded,
+	/// A curator was proposed, and is waiting to accept.
+	CuratorProposed { curator: AccountID, fee: Balance },
+	/// The curator accepted, and works on awarding the bounty.
+	Active { curator: AccountID, fee: Balance },
+	/// The curator awarded the bounty to a beneficiary, who can claim it from the `unlock_at`
+	/// block.
+	PendingPayout {
+		curator: AccountID,
+		fee: Balance,
+		beneficiary: AccountID,
+		unlock_at: BlockNumber,
+	},
+}
+
+/// A bounty, paying out for a task done for the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounty<AccountID, Balance, BlockNumber> {
+	/// The account which proposed the bounty.
+	pub proposer: AccountID,
+	/// The amount paid out, including the fee of the curator.
+	pub value: Balance,
+	/// The amount reserved from the proposer.
+	pub bond: Balance,
+	/// What the bounty is for.
+	pub description: String,
+	/// The status of the bounty.
+	pub status: BountyStatus<AccountID, Balance, BlockNumber>,
+}
+
+/// The bounty type used by a runtime `T`.
+pub type BountyOf<T> = Bounty<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// A bounty was proposed.
+	BountyProposed { index: BountyIndex },
+	/// A bounty was approved and funded by the treasury.
+	BountyApproved { index: BountyIndex },
+	/// A curator was proposed for a bounty.
+	CuratorProposed { index: BountyIndex, curator: AccountID },
+	/// The proposed curator of a bounty accepted.
+	CuratorAccepted { index: BountyIndex, curator: AccountID },
+	/// A bounty was awarded to a beneficiary.
+	BountyAwarded { index: BountyIndex, beneficiary: AccountID },
+	/// A bounty was paid out to its beneficiary.
+	BountyClaimed { index: BountyIndex, payout: Balance, beneficiary: AccountID },
+	/// A bounty was closed before it was awarded.
+	BountyClosed { index: BountyIndex },
+}
+
+/// This is the Bounties Module.
+/// It lets accounts propose bounties, paid out of the treasury for some task. Once approved, a
+/// curator with a fee is assigned to the bounty, and awards it to the beneficiary who did the
+/// task. The beneficiary can claim the payout after a delay.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of bounties which have been proposed, used as the index of the next bounty.
+	bounty_count: BountyIndex,
+	/// A map from a bounty index to the bounty, until it is claimed or closed.
+	bounties: BTreeMap<BountyIndex, BountyOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Bounties Module.
+	pub fn new() -> Self {
+		Self { bounty_count: 0, bounties: BTreeMap::new() }
+	}
+
+	/// Get the account which holds the funds of a bounty once it is approved.
+	pub fn bounty_account_id(index: BountyIndex) -> T::AccountID {
+		T::AccountID::derive_account(&("bounty", index))
+	}
+
+	/// Get a bounty.
+	pub fn bounty(&self, index: BountyIndex) -> Option<&BountyOf<T>> {
+		self.bounties.get(&index)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	fn bounty_mut(&mut self, index: BountyIndex) -> Result<&mut BountyOf<T>, &'static str> {
+		self.bounties.get_mut(&index).ok_or("Bounty not found")
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose a bounty of `value` for the task in `description`.
+	/// The `BOUNTY_DEPOSIT` is reserved from the `caller` until the bounty is approved or closed.
+	pub fn propose_bounty(
+		runtime: &mut T,
+		caller: T::AccountID,
+		value: T::Balance,
+		description: String,
+	) -> DispatchResult {
+		if description.len() > T::MAX_DESCRIPTION_LENGTH {
+			return Err("Description is too long");
+		}
+		let index = GetPallet::<Pallet<T>>::pallet(runtime).bounty_count;
+		let bounty_count = index.checked_add(1).ok_or("Bounty count overflow")?;
+		let bond = T::BOUNTY_DEPOSIT;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, bond)?;
+
+		let bounties = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		bounties.bounty_count = bounty_count;
+		let status = BountyStatus::Proposed;
+		let bounty = Bounty { proposer: caller, value, bond, description, status };
+		bounties.bounties.insert(index, bounty);
+		Self::deposit_event(runtime, Event::BountyProposed { index });
+		Ok(())
+	}
+
+	/// Approve a proposed bounty, moving its value from the treasury to the bounty account and
+	/// returning the deposit of the proposer.
+	/// Only the `ApproveOrigin` can approve bounties.
+	pub fn approve_bounty(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: BountyIndex,
+	) -> DispatchResult {
+		<T as Config>::ApproveOrigin::ensure_origin(runtime, &caller)?;
+		let bounties = GetPallet::<Pallet<T>>::pallet(runtime);
+		let bounty = bounties.bounty(index).ok_or("Bounty not found")?;
+		if bounty.status != BountyStatus::Proposed {
+			return Err("Unexpected status");
+		}
+		let (proposer, value, bond) = (bounty.proposer.clone(), bounty.value, bounty.bond);
+		if treasury::Pallet::<T>::pot(runtime) < value {
+			return Err("Insufficient treasury funds");
+		}
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		let treasury_account = treasury::Pallet::<T>::account_id();
+		balances.do_transfer(treasury_account, Self::bounty_account_id(index), value)?;
+		balances.unreserve(&proposer, bond);
+		let bounty = GetPallet::<Pallet<T>>::pallet_mut(runtime).bounty_mut(index)?;
+		bounty.status = BountyStatus::Funded;
+		Self::deposit_event(runtime, Event::BountyApproved { index });
+		Ok(())
+	}
+
+	/// Propose `curator` for a funded bounty, who will be paid `fee` out of its value.
+	/// Only the `ApproveOrigin` can propose curators.
+	pub fn propose_curator(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: BountyIndex,
+		curator: T::AccountID,
+		fee: T::Balance,
+	) -> DispatchResult {
+		<T as Config>::ApproveOrigin::ensure_origin(runtime, &caller)?;
+		let bounty = GetPallet::<Pallet<T>>::pallet_mut(runtime).bounty_mut(index)?;
+		if bounty.status != BountyStatus::Funded {
+			return Err("Unexpected status");
+		}
+		if fee >= bounty.value {
+			return Err("Invalid fee");
+		}
+		bounty.status = BountyStatus::CuratorProposed { curator: curator.clone(), fee };
+		Self::deposit_event(runtime, Event::CuratorProposed { index, curator });
+		Ok(())
+	}
+
+	/// Accept to curate a bounty, as its proposed curator.
+	/// The `CURATOR_DEPOSIT` is reserved from the `caller` until the bounty is claimed or closed.
+	pub fn accept_curator(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: BountyIndex,
+	) -> DispatchResult {
+		let bounties = GetPallet::<Pallet<T>>::pallet(runtime);
+		let bounty = bounties.bounty(index).ok_or("Bounty not found")?;
+		let fee = match &bounty.status {
+			BountyStatus::CuratorProposed { curator, fee } if curator == &caller => *fee,
+			BountyStatus::CuratorProposed { .. } => return Err("Require curator"),
+			_ => return Err("Unexpected status"),
+		};
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.reserve(&caller, T::CURATOR_DEPOSIT)?;
+		let bounty = GetPallet::<Pallet<T>>::pallet_mut(runtime).bounty_mut(index)?;
+		bounty.status = BountyStatus::Active { curator: caller.clone(), fee };
+		Self::deposit_event(runtime, Event::CuratorAccepted { index, curator: caller });
+		Ok(())
+	}
+
+	/// Award an active bounty to `beneficiary`, as its curator. The beneficiary can claim it after
+	/// `PAYOUT_DELAY` blocks.
+	pub fn award_bounty(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: BountyIndex,
+		beneficiary: T::AccountID,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let unlock_at = now.checked_add(&T::PAYOUT_DELAY).ok_or("Block number overflow")?;
+		let bounty = GetPallet::<Pallet<T>>::pallet_mut(runtime).bounty_mut(index)?;
+		let (curator, fee) = match &bounty.status {
+			BountyStatus::Active { curator, fee } if curator == &caller => (curator.clone(), *fee),
+			BountyStatus::Active { .. } => return Err("Require curator"),
+			_ => return Err("Unexpected status"),
+		};
+
+		bounty.status = BountyStatus::PendingPayout {
+			curator,
+			fee,
+			beneficiary: beneficiary.clone(),
+			unlock_at,
+		};
+		Self::deposit_event(runtime, Event::BountyAwarded { index, beneficiary });
+		Ok(())
+	}
+
+	/// Pay out an awarded bounty once its payout delay has passed: the curator gets its fee and
+	/// deposit back, and the beneficiary gets the rest. Anyone can claim a bounty.
+	pub fn claim_bounty(
+		runtime: &mut T,
+		_caller: T::AccountID,
+		index: BountyIndex,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let bounties = GetPallet::<Pallet<T>>::pallet(runtime);
+		let bounty = bounties.bounty(index).ok_or("Bounty not found")?;
+		let BountyStatus::PendingPayout { curator, fee, beneficiary, unlock_at } = &bounty.status
+		else {
+			return Err("Unexpected status");
+		};
+		if now < *unlock_at {
+			return Err("Premature");
+		}
+		let (curator, fee, beneficiary) = (curator.clone(), *fee, beneficiary.clone());
+		let payout = bounty.value.checked_sub(&fee).ok_or("Invalid fee")?;
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		let bounty_account = Self::bounty_account_id(index);
+		balances.do_transfer(bounty_account.clone(), curator.clone(), fee)?;
+		balances.do_transfer(bounty_account, beneficiary.clone(), payout)?;
+		balances.unreserve(&curator, T::CURATOR_DEPOSIT);
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).bounties.remove(&index);
+		Self::deposit_event(runtime, Event::BountyClaimed { index, payout, beneficiary });
+		Ok(())
+	}
+
+	/// Close a bounty which was not awarded yet. The deposit of the proposer is slashed if the
+	/// bounty was not approved, otherwise its funds go back to the treasury and the deposit of the
+	/// curator, if any, is returned.
+	/// Only the `ApproveOrigin` can close bounties.
+	pub fn close_bounty(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: BountyIndex,
+	) -> DispatchResult {
+		<T as Config>::ApproveOrigin::ensure_origin(runtime, &caller)?;
+		let bounties = GetPallet::<Pallet<T>>::pallet(runtime);
+		let bounty = bounties.bounty(index).ok_or("Bounty not found")?;
+		let bounty = bounty.clone();
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		match bounty.status {
+			BountyStatus::Proposed => {
+				balances.slash_reserved(&bounty.proposer, bounty.bond);
+			},
+			BountyStatus::Funded | BountyStatus::CuratorProposed { .. } => {
+				let treasury_account = treasury::Pallet::<T>::account_id();
+				let bounty_account = Self::bounty_account_id(index);
+				balances.do_transfer(bounty_account, treasury_account, bounty.value)?;
+			},
+			BountyStatus::Active { curator, .. } => {
+				let treasury_account = treasury::Pallet::<T>::account_id();
+				let bounty_account = Self::bounty_account_id(index);
+				balances.do_transfer(bounty_account, treasury_account, bounty.value)?;
+				balances.unreserve(&curator, T::CURATOR_DEPOSIT);
+			},
+			BountyStatus::PendingPayout { .. } => return Err("Pending payout"),
+		}
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).bounties.remove(&index);
+		Self::deposit_event(runtime, Event::BountyClosed { index });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::BountyStatus;
+	use crate::{new_test_runtime, run_to_block, support, treasury, Runtime};
+
+	type Bounties = super::Pallet<Runtime>;
+	type Treasury = treasury::Pallet<Runtime>;
+
+	#[test]
+	fn approve_bounty() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let result = Bounties::propose_bounty(&mut runtime, "alice".into(), 50, "Fix it".into());
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let root = support::root_account::<String>();
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+
+		assert_eq!(Bounties::approve_bounty(&mut runtime, alice.clone(), 0), Err("Bad origin"));
+		let result = Bounties::propose_curator(&mut runtime, root.clone(), 0, "bob".into(), 10);
+		assert_eq!(result, Err("Unexpected status"));
+		assert_eq!(Bounties::approve_bounty(&mut runtime, root.clone(), 0), Ok(()));
+		let result = Bounties::approve_bounty(&mut runtime, root.clone(), 0);
+		assert_eq!(result, Err("Unexpected status"));
+		assert_eq!(runtime.bounties.bounty(0).unwrap().status, BountyStatus::Funded);
+		assert_eq!(runtime.balances.balance(&Bounties::bounty_account_id(0)), 50);
+		assert_eq!(Treasury::pot(&runtime), 50);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+
+		let result = Bounties::propose_bounty(&mut runtime, alice, 60, "Too much".into());
+		assert_eq!(result, Ok(()));
+		let result = Bounties::approve_bounty(&mut runtime, root, 1);
+		assert_eq!(result, Err("Insufficient treasury funds"));
+	}
+
+	#[test]
+	fn award_and_claim_bounty() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let result = Bounties::propose_bounty(&mut runtime, "alice".into(), 50, "Fix it".into());
+		assert_eq!(result, Ok(()));
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let root = support::root_account::<String>();
+
+		assert_eq!(Bounties::approve_bounty(&mut runtime, root.clone(), 0), Ok(()));
+		let result = Bounties::propose_curator(&mut runtime, root.clone(), 0, bob.clone(), 50);
+		assert_eq!(result, Err("Invalid fee"));
+		assert_eq!(Bounties::propose_curator(&mut runtime, root, 0, bob.clone(), 10), Ok(()));
+		let result = Bounties::accept_curator(&mut runtime, charlie.clone(), 0);
+		assert_eq!(result, Err("Require curator"));
+		assert_eq!(Bounties::accept_curator(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 5);
+
+		let result = Bounties::award_bounty(&mut runtime, charlie.clone(), 0, charlie.clone());
+		assert_eq!(result, Err("Require curator"));
+		assert_eq!(Bounties::award_bounty(&mut runtime, bob.clone(), 0, charlie.clone()), Ok(()));
+		assert_eq!(Bounties::claim_bounty(&mut runtime, charlie.clone(), 0), Err("Premature"));
+
+		run_to_block(&mut runtime, 3);
+		assert_eq!(Bounties::claim_bounty(&mut runtime, charlie.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.balance(&charlie), 40);
+		assert_eq!(runtime.balances.balance(&bob), 110);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert!(runtime.bounties.bounty(0).is_none());
+	}
+
+	#[test]
+	fn close_bounty() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let result = Bounties::propose_bounty(&mut runtime, "alice".into(), 50, "Fix it".into());
+		assert_eq!(result, Ok(()));
+		let root = support::root_account::<String>();
+
+		// Closing a proposed bounty slashes the deposit of the proposer into the treasury.
+		assert_eq!(Bounties::close_bounty(&mut runtime, root.clone(), 0), Ok(()));
+		assert_eq!(Treasury::pot(&runtime), 110);
+		assert_eq!(runtime.balances.reserved_balance(&"alice".to_string()), 0);
+
+		let result = Bounties::propose_bounty(&mut runtime, "alice".into(), 50, "Fix it".into());
+		assert_eq!(result, Ok(()));
+		assert_eq!(Bounties::approve_bounty(&mut runtime, root.clone(), 1), Ok(()));
+		assert_eq!(Bounties::close_bounty(&mut runtime, root.clone(), 1), Ok(()));
+		assert_eq!(Treasury::pot(&runtime), 110);
+		assert_eq!(Bounties::close_bounty(&mut runtime, root, 1), Err("Bounty not found"));
+	}
+}
@@ -0,0 +1,453 @@
+use crate::{
+	support::{
+		self, CallHash, ChangeMembers, DeriveAccount, Dispatch, DispatchResult, EnsureOrigin,
+		GetPallet,
+	},
+	system,
+};
+use core::{fmt::Debug, hash::Hash, marker::PhantomData};
+use num::traits::CheckedAdd;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<AccountID: DeriveAccount, RuntimeEvent: From<Event<Self::AccountID>>>
+	+ Sized
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which can be proposed.
+	type RuntimeCall: Clone + Debug + Hash;
+	/// The number of blocks a proposal can be voted on before it can be closed without reaching
+	/// its threshold.
+	const MOTION_DURATION: Self::BlockNumber;
+	/// The maximum number of proposals being voted on at the same time.
+	const MAX_PROPOSALS: usize;
+}
+
+/// The index of a proposal.
+pub type ProposalIndex = u32;
+
+/// A number of members of the collective.
+pub type MemberCount = u32;
+
+/// The votes on a proposal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Votes<AccountID, BlockNumber> {
+	/// The index of the proposal.
+	pub index: ProposalIndex,
+	/// The number of aye votes needed to approve the proposal.
+	pub threshold: MemberCount,
+	/// The members which voted aye.
+	pub ayes: Vec<AccountID>,
+	/// The members which voted nay.
+	pub nays: Vec<AccountID>,
+	/// The block after which the proposal can be closed without reaching its threshold.
+	pub end: BlockNumber,
+}
+
+/// The votes type used by a runtime `T`.
+pub type VotesOf<T> = Votes<<T as system::Config>::AccountID, <T as system::Config>::BlockNumber>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// A member proposed a call, which needs `threshold` aye votes to be approved.
+	Proposed {
+		account: AccountID,
+		proposal_index: ProposalIndex,
+		proposal_hash: CallHash,
+		threshold: MemberCount,
+	},
+	/// A member voted on a proposal, which now has `yes` aye votes and `no` nay votes.
+	Voted { account: AccountID, proposal_hash: CallHash, voted: bool, yes: u32, no: u32 },
+	/// A proposal was closed with `yes` aye votes and `no` nay votes.
+	Closed { proposal_hash: CallHash, yes: u32, no: u32 },
+	/// A proposal was approved.
+	Approved { proposal_hash: CallHash },
+	/// A proposal was not approved.
+	Disapproved { proposal_hash: CallHash },
+	/// An approved proposal was dispatched, with the given `result`.
+	Executed { proposal_hash: CallHash, result: DispatchResult },
+}
+
+/// This is the Collective Module.
+/// It lets a set of members, like a council, vote on calls. An approved call is dispatched from an
+/// account of the collective, and the collective remembers how many members approved it, so that
+/// other pallets can require the approval of a proportion of the members with
+/// `EnsureProportionAtLeast`.
+///
+/// The members are only ever changed through `ChangeMembers`, by the membership pallet, so that
+/// both pallets always agree on them.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The members of the collective, sorted.
+	members: Vec<T::AccountID>,
+	/// The number of proposals which have been made, used as the index of the next proposal.
+	proposal_count: ProposalIndex,
+	/// A map from the hash of a proposal to the proposed call.
+	proposals: BTreeMap<CallHash, T::RuntimeCall>,
+	/// A map from the hash of a proposal to the votes on it.
+	voting: BTreeMap<CallHash, VotesOf<T>>,
+	/// A map from an account which dispatched approved proposals to the number of members which
+	/// approved them, and the number of members at the time.
+	approval_accounts: BTreeMap<T::AccountID, (MemberCount, MemberCount)>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Collective Module.
+	pub fn new() -> Self {
+		Self {
+			members: Vec::new(),
+			proposal_count: 0,
+			proposals: BTreeMap::new(),
+			voting: BTreeMap::new(),
+			approval_accounts: BTreeMap::new(),
+		}
+	}
+
+	/// Get the account which dispatches the proposals approved by `ayes` out of `seats` members.
+	pub fn approval_account_id(ayes: MemberCount, seats: MemberCount) -> T::AccountID {
+		T::AccountID::derive_account(&("collective", ayes, seats))
+	}
+
+	/// Get the members of the collective.
+	pub fn members(&self) -> &[T::AccountID] {
+		&self.members
+	}
+
+	/// Get the votes on a proposal.
+	pub fn voting(&self, proposal_hash: CallHash) -> Option<&VotesOf<T>> {
+		self.voting.get(&proposal_hash)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	fn ensure_member(&self, who: &T::AccountID) -> DispatchResult {
+		if self.members.binary_search(who).is_err() {
+			return Err("Not a member");
+		}
+		Ok(())
+	}
+
+	/// Replace the members with the sorted `new_members`, removing the votes of the outgoing
+	/// members from the proposals being voted on.
+	fn replace_members(&mut self, new_members: Vec<T::AccountID>) {
+		for votes in self.voting.values_mut() {
+			votes.ayes.retain(|who| new_members.binary_search(who).is_ok());
+			votes.nays.retain(|who| new_members.binary_search(who).is_ok());
+		}
+		self.members = new_members;
+	}
+
+	/// Dispatch a proposal approved by `ayes` members from the matching account of the collective.
+	fn execute(
+		runtime: &mut T,
+		proposal_hash: CallHash,
+		proposal: T::RuntimeCall,
+		ayes: MemberCount,
+	) {
+		let collective = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let seats = collective.members.len() as MemberCount;
+		let account = Self::approval_account_id(ayes, seats);
+		collective.approval_accounts.insert(account.clone(), (ayes, seats));
+		let result = runtime.dispatch(account, proposal);
+		Self::deposit_event(runtime, Event::Executed { proposal_hash, result });
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+/// An `EnsureOrigin` implementation which only allows the calls approved by at least `N / D` of
+/// the members of the collective.
+pub struct EnsureProportionAtLeast<T, const N: MemberCount, const D: MemberCount>(PhantomData<T>);
+
+impl<T: Config, const N: MemberCount, const D: MemberCount> EnsureOrigin<T, T::AccountID>
+	for EnsureProportionAtLeast<T, N, D>
+{
+	fn ensure_origin(runtime: &T, caller: &T::AccountID) -> DispatchResult {
+		let collective = GetPallet::<Pallet<T>>::pallet(runtime);
+		match collective.approval_accounts.get(caller) {
+			Some((ayes, seats)) if ayes * D >= N * seats => Ok(()),
+			_ => Err("Bad origin"),
+		}
+	}
+}
+
+/// Lets another pallet, like the membership pallet, maintain the members of the collective.
+impl<T: Config> ChangeMembers<T, T::AccountID> for Pallet<T> {
+	fn change_members_sorted(
+		runtime: &mut T,
+		_incoming: &[T::AccountID],
+		_outgoing: &[T::AccountID],
+		sorted_new: &[T::AccountID],
+	) {
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).replace_members(sorted_new.to_vec());
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose the `proposal` call, which needs `threshold` aye votes to be approved. The `caller`
+	/// votes aye. If the `threshold` is one, the call is dispatched immediately, approved by the
+	/// `caller` alone.
+	pub fn propose(
+		runtime: &mut T,
+		caller: T::AccountID,
+		threshold: MemberCount,
+		proposal: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		let collective = GetPallet::<Pallet<T>>::pallet(runtime);
+		collective.ensure_member(&caller)?;
+		let proposal_hash = support::hash_of(&proposal);
+		if collective.proposals.contains_key(&proposal_hash) {
+			return Err("Duplicate proposal");
+		}
+
+		if threshold == 0 || threshold as usize > collective.members.len() {
+			return Err("Invalid threshold");
+		}
+		if threshold == 1 {
+			Self::execute(runtime, proposal_hash, *proposal, 1);
+			return Ok(());
+		}
+
+		if collective.proposals.len() >= T::MAX_PROPOSALS {
+			return Err("Too many proposals");
+		}
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let end = now.checked_add(&T::MOTION_DURATION).ok_or("Overflow error")?;
+
+		let collective = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let proposal_index = collective.proposal_count;
+		collective.proposal_count = proposal_index.checked_add(1).ok_or("Overflow error")?;
+		let ayes = vec![caller.clone()];
+		let votes = Votes { index: proposal_index, threshold, ayes, nays: vec![], end };
+		collective.proposals.insert(proposal_hash, *proposal);
+		collective.voting.insert(proposal_hash, votes);
+
+		let event = Event::Proposed { account: caller, proposal_index, proposal_hash, threshold };
+		Self::deposit_event(runtime, event);
+		Ok(())
+	}
+
+	/// Vote on the proposal with the given hash and `index`, replacing any previous vote of the
+	/// `caller` on it.
+	pub fn vote(
+		runtime: &mut T,
+		caller: T::AccountID,
+		proposal_hash: CallHash,
+		index: ProposalIndex,
+		approve: bool,
+	) -> DispatchResult {
+		let collective = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		collective.ensure_member(&caller)?;
+		let votes = collective.voting.get_mut(&proposal_hash).ok_or("Proposal not found")?;
+		if votes.index != index {
+			return Err("Mismatched index");
+		}
+
+		let (same, other) = match approve {
+			true => (&mut votes.ayes, &mut votes.nays),
+			false => (&mut votes.nays, &mut votes.ayes),
+		};
+		if same.contains(&caller) {
+			return Err("Duplicate vote");
+		}
+		other.retain(|who| who != &caller);
+		same.push(caller.clone());
+
+		let (yes, no) = (votes.ayes.len() as u32, votes.nays.len() as u32);
+		let event = Event::Voted { account: caller, proposal_hash, voted: approve, yes, no };
+		Self::deposit_event(runtime, event);
+		Ok(())
+	}
+
+	/// Close the proposal with the given hash and `index`, dispatching it if it was approved.
+	/// A proposal can be closed once it has reached its threshold, once it can no longer reach its
+	/// threshold, or after `MOTION_DURATION` blocks.
+	pub fn close(
+		runtime: &mut T,
+		_caller: T::AccountID,
+		proposal_hash: CallHash,
+		index: ProposalIndex,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let collective = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let votes = collective.voting.get(&proposal_hash).ok_or("Proposal not found")?;
+		if votes.index != index {
+			return Err("Mismatched index");
+		}
+
+		let (yes, no) = (votes.ayes.len() as u32, votes.nays.len() as u32);
+		let seats = collective.members.len() as u32;
+		let approved = yes >= votes.threshold;
+		let disapproved = seats.saturating_sub(no) < votes.threshold;
+		if !approved && !disapproved && now < votes.end {
+			return Err("Too early to close");
+		}
+
+		collective.voting.remove(&proposal_hash);
+		let proposal = collective.proposals.remove(&proposal_hash).ok_or("Proposal not found")?;
+		Self::deposit_event(runtime, Event::Closed { proposal_hash, yes, no });
+		if approved {
+			Self::deposit_event(runtime, Event::Approved { proposal_hash });
+			Self::execute(runtime, proposal_hash, proposal, yes);
+		} else {
+			Self::deposit_event(runtime, Event::Disapproved { proposal_hash });
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{
+		membership, new_test_runtime, run_to_block, support, treasury, Runtime, RuntimeCall,
+		RuntimeEvent,
+	};
+
+	type Collective = super::Pallet<Runtime>;
+	type Membership = membership::Pallet<Runtime>;
+
+	fn approve_spend() -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::treasury(treasury::Call::approve_proposal { proposal_index: 0 }))
+	}
+
+	#[test]
+	fn members_are_changed_by_membership() {
+		let mut runtime = new_test_runtime(&[("dave", 100)]);
+		let members = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Membership::reset_members(&mut runtime, support::root_account(), members);
+		assert_eq!(result, Ok(()));
+		let dave = "dave".to_string();
+		let result = treasury::Pallet::propose_spend(&mut runtime, dave.clone(), 50, dave);
+		assert_eq!(result, Ok(()));
+		let hash = support::hash_of(&approve_spend());
+		assert_eq!(Collective::propose(&mut runtime, "alice".into(), 3, approve_spend()), Ok(()));
+		assert_eq!(Collective::vote(&mut runtime, "bob".into(), hash, 0, true), Ok(()));
+
+		// The outgoing members lose their votes.
+		let root = support::root_account::<String>();
+		let members = vec!["dave".to_string(), "alice".to_string(), "dave".to_string()];
+		assert_eq!(Membership::reset_members(&mut runtime, root, members), Ok(()));
+		assert_eq!(runtime.collective.members(), &["alice".to_string(), "dave".to_string()]);
+		assert_eq!(runtime.collective.members(), runtime.membership.members());
+		assert_eq!(runtime.collective.voting(hash).unwrap().ayes, vec!["alice".to_string()]);
+	}
+
+	#[test]
+	fn approved_proposal_is_dispatched_by_collective() {
+		let mut runtime = new_test_runtime(&[("dave", 100)]);
+		let members = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Membership::reset_members(&mut runtime, support::root_account(), members);
+		assert_eq!(result, Ok(()));
+		let dave = "dave".to_string();
+		let result = treasury::Pallet::propose_spend(&mut runtime, dave.clone(), 50, dave);
+		assert_eq!(result, Ok(()));
+		let hash = support::hash_of(&approve_spend());
+
+		// The treasury only accepts approvals from root or the collective.
+		let result = Collective::propose(&mut runtime, "dave".into(), 2, approve_spend());
+		assert_eq!(result, Err("Not a member"));
+		assert_eq!(Collective::propose(&mut runtime, "alice".into(), 2, approve_spend()), Ok(()));
+		let result = Collective::propose(&mut runtime, "bob".into(), 2, approve_spend());
+		assert_eq!(result, Err("Duplicate proposal"));
+
+		let result = Collective::close(&mut runtime, "alice".into(), hash, 0);
+		assert_eq!(result, Err("Too early to close"));
+		let result = Collective::vote(&mut runtime, "bob".into(), hash, 1, true);
+		assert_eq!(result, Err("Mismatched index"));
+		assert_eq!(Collective::vote(&mut runtime, "bob".into(), hash, 0, true), Ok(()));
+		let result = Collective::vote(&mut runtime, "bob".into(), hash, 0, true);
+		assert_eq!(result, Err("Duplicate vote"));
+
+		assert_eq!(Collective::close(&mut runtime, "dave".into(), hash, 0), Ok(()));
+		assert!(runtime.collective.voting(hash).is_none());
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::collective(Event::Executed {
+				proposal_hash: hash,
+				result: Ok(())
+			}))
+		);
+	}
+
+	#[test]
+	fn proposal_needs_enough_approvals() {
+		let mut runtime = new_test_runtime(&[("dave", 100)]);
+		let members = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Membership::reset_members(&mut runtime, support::root_account(), members);
+		assert_eq!(result, Ok(()));
+		let dave = "dave".to_string();
+		let result = treasury::Pallet::propose_spend(&mut runtime, dave.clone(), 50, dave);
+		assert_eq!(result, Ok(()));
+
+		let result = Collective::propose(&mut runtime, "alice".into(), 0, approve_spend());
+		assert_eq!(result, Err("Invalid threshold"));
+		let result = Collective::propose(&mut runtime, "alice".into(), 4, approve_spend());
+		assert_eq!(result, Err("Invalid threshold"));
+
+		// A single member can dispatch a call, but not as an approval of the collective.
+		assert_eq!(Collective::propose(&mut runtime, "alice".into(), 1, approve_spend()), Ok(()));
+		assert!(runtime.treasury.approvals().is_empty());
+		let hash = support::hash_of(&approve_spend());
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::collective(Event::Executed {
+				proposal_hash: hash,
+				result: Err("Bad origin")
+			}))
+		);
+	}
+
+	#[test]
+	fn disapproved_proposal() {
+		let mut runtime = new_test_runtime(&[("dave", 100)]);
+		let members = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Membership::reset_members(&mut runtime, support::root_account(), members);
+		assert_eq!(result, Ok(()));
+		let dave = "dave".to_string();
+		let result = treasury::Pallet::propose_spend(&mut runtime, dave.clone(), 50, dave);
+		assert_eq!(result, Ok(()));
+		let hash = support::hash_of(&approve_spend());
+
+		assert_eq!(Collective::propose(&mut runtime, "alice".into(), 3, approve_spend()), Ok(()));
+		assert_eq!(Collective::vote(&mut runtime, "bob".into(), hash, 0, true), Ok(()));
+		// Bob changes his mind, so the proposal can no longer reach its threshold.
+		assert_eq!(Collective::vote(&mut runtime, "bob".into(), hash, 0, false), Ok(()));
+		let votes = runtime.collective.voting(hash).unwrap();
+		assert_eq!((votes.ayes.len(), votes.nays.len()), (1, 1));
+
+		assert_eq!(Collective::close(&mut runtime, "alice".into(), hash, 0), Ok(()));
+		assert!(runtime.treasury.approvals().is_empty());
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::collective(Event::Disapproved { proposal_hash: hash }))
+		);
+	}
+
+	#[test]
+	fn close_after_motion_duration() {
+		let mut runtime = new_test_runtime(&[("dave", 100)]);
+		let members = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		let result = Membership::reset_members(&mut runtime, support::root_account(), members);
+		assert_eq!(result, Ok(()));
+		let dave = "dave".to_string();
+		let result = treasury::Pallet::propose_spend(&mut runtime, dave.clone(), 50, dave);
+		assert_eq!(result, Ok(()));
+		let hash = support::hash_of(&approve_spend());
+
+		assert_eq!(Collective::propose(&mut runtime, "alice".into(), 2, approve_spend()), Ok(()));
+		run_to_block(&mut runtime, 4);
+		let result = Collective::close(&mut runtime, "alice".into(), hash, 0);
+		assert_eq!(result, Err("Too early to close"));
+		run_to_block(&mut runtime, 5);
+		assert_eq!(Collective::close(&mut runtime, "alice".into(), hash, 0), Ok(()));
+		assert!(runtime.treasury.approvals().is_empty());
+	}
+}
@@ -0,0 +1,317 @@
+use crate::{
+	balances,
+	support::{self, DeriveAccount, DispatchResult, GetPallet},
+	system,
+};
+use num::{traits::CheckedAdd, Zero};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<
+		AccountID: DeriveAccount,
+		RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>,
+	> + balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The minimum amount of a contribution.
+	const MIN_CONTRIBUTION: Self::Balance;
+}
+
+/// The index of a fund.
+pub type FundIndex = u32;
+
+/// A fundraising campaign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fund<AccountID, Balance, BlockNumber> {
+	/// The account which receives the funds if the campaign succeeds.
+	pub beneficiary: AccountID,
+	/// The amount to raise. The campaign succeeds if it is reached by the end block, and no more
+	/// can be contributed once it is.
+	pub cap: Balance,
+	/// The block at which the campaign ends.
+	pub end: BlockNumber,
+	/// The amount contributed so far.
+	pub raised: Balance,
+	/// The amount contributed by each account, until it is refunded.
+	pub contributions: BTreeMap<AccountID, Balance>,
+}
+
+/// The fund type used by a runtime `T`.
+pub type FundOf<T> = Fund<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// A fund was created.
+	Created { fund_index: FundIndex },
+	/// An account contributed to a fund.
+	Contributed { fund_index: FundIndex, who: AccountID, amount: Balance },
+	/// The beneficiary of a successful fund withdrew the funds.
+	Withdrawn { fund_index: FundIndex, amount: Balance },
+	/// A contributor to a failed fund got its contribution back.
+	Refunded { fund_index: FundIndex, who: AccountID, amount: Balance },
+}
+
+/// This is the Crowdfund Module.
+/// It lets anyone create a fundraising campaign with a cap and an end block. Contributions are
+/// held in an account derived for the fund. If the cap is reached by the end, the beneficiary
+/// withdraws them, otherwise every contributor is refunded.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of funds which have been created, used as the index of the next fund.
+	fund_count: FundIndex,
+	/// A map from a fund index to the fund, until it is withdrawn or fully refunded.
+	funds: BTreeMap<FundIndex, FundOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Crowdfund Module.
+	pub fn new() -> Self {
+		Self { fund_count: 0, funds: BTreeMap::new() }
+	}
+
+	/// Get the account which holds the contributions to a fund.
+	pub fn fund_account_id(fund_index: FundIndex) -> T::AccountID {
+		T::AccountID::derive_account(&("crowdfund", fund_index))
+	}
+
+	/// Get a fund.
+	pub fn fund(&self, fund_index: FundIndex) -> Option<&FundOf<T>> {
+		self.funds.get(&fund_index)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Get a fund, checking that it has ended.
+	fn ended_fund(runtime: &T, fund_index: FundIndex) -> Result<&FundOf<T>, &'static str> {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let crowdfund = GetPallet::<Pallet<T>>::pallet(runtime);
+		let fund = crowdfund.fund(fund_index).ok_or("Fund not found")?;
+		if now < fund.end {
+			return Err("Fund has not ended");
+		}
+		Ok(fund)
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create a fund raising up to `cap` for the `beneficiary`, until the `end` block.
+	pub fn create(
+		runtime: &mut T,
+		_caller: T::AccountID,
+		beneficiary: T::AccountID,
+		cap: T::Balance,
+		end: T::BlockNumber,
+	) -> DispatchResult {
+		if cap.is_zero() {
+			return Err("Cap is zero");
+		}
+		if end <= GetPallet::<system::Pallet<T>>::pallet(runtime).block_number() {
+			return Err("End is in the past");
+		}
+		let crowdfund = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let fund_index = crowdfund.fund_count;
+		crowdfund.fund_count = fund_index.checked_add(1).ok_or("Fund count overflow")?;
+		let fund = Fund {
+			beneficiary,
+			cap,
+			end,
+			raised: T::Balance::zero(),
+			contributions: BTreeMap::new(),
+		};
+		crowdfund.funds.insert(fund_index, fund);
+
+		Self::deposit_event(runtime, Event::Created { fund_index });
+		Ok(())
+	}
+
+	/// Contribute `value` to a fund which has not ended, transferring it to the fund account.
+	/// The `value` must be at least `MIN_CONTRIBUTION`, unless it is exactly what the fund needs
+	/// to reach its cap.
+	pub fn contribute(
+		runtime: &mut T,
+		caller: T::AccountID,
+		fund_index: FundIndex,
+		value: T::Balance,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let crowdfund = GetPallet::<Pallet<T>>::pallet(runtime);
+		let fund = crowdfund.fund(fund_index).ok_or("Fund not found")?;
+		if now >= fund.end {
+			return Err("Fund has ended");
+		}
+		let raised = fund.raised.checked_add(&value).ok_or("Balance overflow error")?;
+		if raised > fund.cap {
+			return Err("Contribution exceeds the cap");
+		}
+		if value.is_zero() || (value < T::MIN_CONTRIBUTION && raised != fund.cap) {
+			return Err("Contribution is too small");
+		}
+		let contribution = fund.contributions.get(&caller).copied().unwrap_or(T::Balance::zero());
+		let contribution = contribution.checked_add(&value).ok_or("Balance overflow error")?;
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.do_transfer(caller.clone(), Self::fund_account_id(fund_index), value)?;
+		let crowdfund = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if let Some(fund) = crowdfund.funds.get_mut(&fund_index) {
+			fund.raised = raised;
+			fund.contributions.insert(caller.clone(), contribution);
+		}
+
+		Self::deposit_event(runtime, Event::Contributed { fund_index, who: caller, amount: value });
+		Ok(())
+	}
+
+	/// Transfer the funds raised by a fund which has ended and reached its cap to its beneficiary.
+	/// Only the beneficiary can withdraw.
+	pub fn withdraw(
+		runtime: &mut T,
+		caller: T::AccountID,
+		fund_index: FundIndex,
+	) -> DispatchResult {
+		let fund = Self::ended_fund(runtime, fund_index)?;
+		if caller != fund.beneficiary {
+			return Err("Not the beneficiary");
+		}
+		if fund.raised < fund.cap {
+			return Err("Fund did not reach its cap");
+		}
+		let amount = fund.raised;
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.do_transfer(Self::fund_account_id(fund_index), caller, amount)?;
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).funds.remove(&fund_index);
+		Self::deposit_event(runtime, Event::Withdrawn { fund_index, amount });
+		Ok(())
+	}
+
+	/// Transfer the contribution of the `caller` to a fund which has ended without reaching its
+	/// cap back to them. The fund is removed once every contributor is refunded.
+	pub fn refund(runtime: &mut T, caller: T::AccountID, fund_index: FundIndex) -> DispatchResult {
+		let fund = Self::ended_fund(runtime, fund_index)?;
+		if fund.raised >= fund.cap {
+			return Err("Fund reached its cap");
+		}
+		let amount = *fund.contributions.get(&caller).ok_or("No contribution")?;
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.do_transfer(Self::fund_account_id(fund_index), caller.clone(), amount)?;
+		let crowdfund = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if let Some(fund) = crowdfund.funds.get_mut(&fund_index) {
+			fund.contributions.remove(&caller);
+			if fund.contributions.is_empty() {
+				crowdfund.funds.remove(&fund_index);
+			}
+		}
+
+		Self::deposit_event(runtime, Event::Refunded { fund_index, who: caller, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{new_test_runtime, run_to_block, Runtime};
+
+	type Crowdfund = super::Pallet<Runtime>;
+
+	#[test]
+	fn contribute() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Crowdfund::create(&mut runtime, "alice".into(), "charlie".into(), 100, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+
+		let result = Crowdfund::create(&mut runtime, alice.clone(), alice.clone(), 0, 10);
+		assert_eq!(result, Err("Cap is zero"));
+		assert_eq!(
+			Crowdfund::contribute(&mut runtime, "alice".into(), 0, 1),
+			Err("Contribution is too small")
+		);
+		assert_eq!(Crowdfund::contribute(&mut runtime, "alice".into(), 0, 40), Ok(()));
+		assert_eq!(Crowdfund::contribute(&mut runtime, "alice".into(), 0, 20), Ok(()));
+		assert_eq!(
+			Crowdfund::contribute(&mut runtime, "bob".into(), 0, 50),
+			Err("Contribution exceeds the cap")
+		);
+		assert_eq!(runtime.balances.balance(&Crowdfund::fund_account_id(0)), 60);
+		let fund = runtime.crowdfund.fund(0).unwrap();
+		assert_eq!((fund.raised, fund.contributions.get(&alice)), (60, Some(&60)));
+
+		run_to_block(&mut runtime, 10);
+		assert_eq!(Crowdfund::contribute(&mut runtime, "bob".into(), 0, 10), Err("Fund has ended"));
+	}
+
+	#[test]
+	fn last_contribution_fills_the_cap() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Crowdfund::create(&mut runtime, "alice".into(), "charlie".into(), 100, 10);
+		assert_eq!(result, Ok(()));
+		assert_eq!(Crowdfund::contribute(&mut runtime, "alice".into(), 0, 97), Ok(()));
+		assert_eq!(
+			Crowdfund::contribute(&mut runtime, "alice".into(), 0, 2),
+			Err("Contribution is too small")
+		);
+
+		// A contribution below the minimum is accepted when it reaches the cap exactly.
+		assert_eq!(Crowdfund::contribute(&mut runtime, "alice".into(), 0, 3), Ok(()));
+		assert_eq!(runtime.crowdfund.fund(0).unwrap().raised, 100);
+		assert_eq!(
+			Crowdfund::contribute(&mut runtime, "alice".into(), 0, 0),
+			Err("Contribution is too small")
+		);
+	}
+
+	#[test]
+	fn withdraw_on_success() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Crowdfund::create(&mut runtime, "alice".into(), "charlie".into(), 100, 10);
+		assert_eq!(result, Ok(()));
+		let charlie = "charlie".to_string();
+		assert_eq!(Crowdfund::contribute(&mut runtime, "alice".into(), 0, 60), Ok(()));
+		assert_eq!(Crowdfund::contribute(&mut runtime, "bob".into(), 0, 40), Ok(()));
+
+		let result = Crowdfund::withdraw(&mut runtime, charlie.clone(), 0);
+		assert_eq!(result, Err("Fund has not ended"));
+		run_to_block(&mut runtime, 10);
+		assert_eq!(Crowdfund::refund(&mut runtime, "bob".into(), 0), Err("Fund reached its cap"));
+		assert_eq!(Crowdfund::withdraw(&mut runtime, "bob".into(), 0), Err("Not the beneficiary"));
+		assert_eq!(Crowdfund::withdraw(&mut runtime, charlie.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.balance(&charlie), 100);
+		assert!(runtime.crowdfund.fund(0).is_none());
+	}
+
+	#[test]
+	fn refund_on_failure() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Crowdfund::create(&mut runtime, "alice".into(), "charlie".into(), 100, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		assert_eq!(Crowdfund::contribute(&mut runtime, alice.clone(), 0, 60), Ok(()));
+		assert_eq!(Crowdfund::contribute(&mut runtime, bob.clone(), 0, 30), Ok(()));
+
+		assert_eq!(Crowdfund::refund(&mut runtime, alice.clone(), 0), Err("Fund has not ended"));
+		run_to_block(&mut runtime, 10);
+		let result = Crowdfund::withdraw(&mut runtime, "charlie".into(), 0);
+		assert_eq!(result, Err("Fund did not reach its cap"));
+		assert_eq!(Crowdfund::refund(&mut runtime, "charlie".into(), 0), Err("No contribution"));
+		assert_eq!(Crowdfund::refund(&mut runtime, alice.clone(), 0), Ok(()));
+		assert_eq!(Crowdfund::refund(&mut runtime, alice.clone(), 0), Err("No contribution"));
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(Crowdfund::refund(&mut runtime, bob.clone(), 0), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert!(runtime.crowdfund.fund(0).is_none());
+	}
+}
@@ -0,0 +1,484 @@
+use crate::{
+	balances::{self, LockIdentifier},
+	support::{self, DeriveAccount, Dispatch, DispatchResult, GetPallet},
+	system,
+};
+use core::fmt::Debug;
+use num::{
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedRem, CheckedSub},
+	Zero,
+};
+use std::collections::BTreeMap;
+
+/// The identifier of the lock this pallet places on the balance used to vote.
+const DEMOCRACY_ID: LockIdentifier = "democracy";
+
+pub trait Config:
+	system::Config<
+		AccountID: DeriveAccount,
+		BlockNumber: CheckedRem + CheckedMul + From<u32>,
+		RuntimeEvent: From<Event<Self::Balance>>,
+	> + balances::Config<Balance: CheckedMul + CheckedDiv + From<u32>>
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which can be proposed.
+	type RuntimeCall: Clone + Debug;
+	/// The number of blocks between the launch of each referendum.
+	const LAUNCH_PERIOD: Self::BlockNumber;
+	/// The number of blocks a referendum can be voted on.
+	const VOTING_PERIOD: Self::BlockNumber;
+	/// The number of blocks the balance used to vote stays locked after the end of a referendum,
+	/// for each lock period of the conviction of the vote.
+	const VOTE_LOCKING_PERIOD: Self::BlockNumber;
+	/// The minimum deposit for a proposal.
+	const MINIMUM_DEPOSIT: Self::Balance;
+	/// The maximum number of proposals waiting to be launched.
+	const MAX_PROPOSALS: usize;
+}
+
+/// The index of a proposal.
+pub type PropIndex = u32;
+
+/// The index of a referendum.
+pub type ReferendumIndex = u32;
+
+/// How strongly an account believes in its vote. Votes with a higher conviction are worth more, but
+/// lock the balance used to vote for longer after the referendum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Conviction {
+	/// A tenth of a vote per unit of balance, without locking the balance after the referendum.
+	None,
+	/// One vote per unit of balance, locked for one period.
+	Locked1x,
+	/// Two votes per unit of balance, locked for two periods.
+	Locked2x,
+	/// Three votes per unit of balance, locked for four periods.
+	Locked3x,
+	/// Four votes per unit of balance, locked for eight periods.
+	Locked4x,
+	/// Five votes per unit of balance, locked for sixteen periods.
+	Locked5x,
+	/// Six votes per unit of balance, locked for thirty two periods.
+	Locked6x,
+}
+
+impl Conviction {
+	/// The number of periods the balance used to vote stays locked after the referendum.
+	pub fn lock_periods(&self) -> u32 {
+		match self {
+			Conviction::None => 0,
+			Conviction::Locked1x => 1,
+			Conviction::Locked2x => 2,
+			Conviction::Locked3x => 4,
+			Conviction::Locked4x => 8,
+			Conviction::Locked5x => 16,
+			Conviction::Locked6x => 32,
+		}
+	}
+
+	/// The number of votes `balance` is worth with this conviction.
+	pub fn votes<Balance>(&self, balance: Balance) -> Balance
+	where
+		Balance: CheckedMul + CheckedDiv + From<u32> + Copy,
+	{
+		let votes = match self {
+			Conviction::None => balance.checked_div(&Balance::from(10)),
+			Conviction::Locked1x => Some(balance),
+			Conviction::Locked2x => balance.checked_mul(&Balance::from(2)),
+			Conviction::Locked3x => balance.checked_mul(&Balance::from(3)),
+			Conviction::Locked4x => balance.checked_mul(&Balance::from(4)),
+			Conviction::Locked5x => balance.checked_mul(&Balance::from(5)),
+			Conviction::Locked6x => balance.checked_mul(&Balance::from(6)),
+		};
+		// If the votes would overflow, the vote is simply worth its balance.
+		votes.unwrap_or(balance)
+	}
+}
+
+/// A vote on a referendum, using `balance` with some `conviction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vote<Balance> {
+	/// Whether the vote is in favour of the proposal.
+	pub aye: bool,
+	/// The balance used to vote, which is locked.
+	pub balance: Balance,
+	/// The conviction of the vote.
+	pub conviction: Conviction,
+}
+
+/// The votes for and against a referendum, weighted by conviction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tally<Balance> {
+	/// The votes in favour of the proposal.
+	pub ayes: Balance,
+	/// The votes against the proposal.
+	pub nays: Balance,
+}
+
+/// A proposal waiting to be launched as a referendum.
+#[derive(Debug, Clone)]
+pub struct PublicProposal<AccountID, Balance, Call> {
+	/// The index of the proposal.
+	pub index: PropIndex,
+	/// The account which made the proposal.
+	pub proposer: AccountID,
+	/// The deposit reserved from the proposer, which is returned when the referendum is launched.
+	pub deposit: Balance,
+	/// The proposed call.
+	pub proposal: Call,
+}
+
+/// A referendum which is being voted on.
+#[derive(Debug, Clone)]
+pub struct Referendum<AccountID, Balance, BlockNumber, Call> {
+	/// The block at which the referendum ends.
+	pub end: BlockNumber,
+	/// The call which is dispatched if the referendum passes.
+	pub proposal: Call,
+	/// The current tally of the votes.
+	pub tally: Tally<Balance>,
+	/// The vote of each account.
+	pub votes: BTreeMap<AccountID, Vote<Balance>>,
+}
+
+/// The proposal type used by a runtime `T`.
+pub type PublicProposalOf<T> = PublicProposal<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as Config>::RuntimeCall,
+>;
+
+/// The referendum type used by a runtime `T`.
+pub type ReferendumOf<T> = Referendum<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+	<T as Config>::RuntimeCall,
+>;
+
+/// The vote lock type used by a runtime `T`: the amount locked, and the block it is locked until.
+type VoteLockOf<T> = (<T as balances::Config>::Balance, <T as system::Config>::BlockNumber);
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<Balance> {
+	/// A proposal was made with some `deposit`.
+	Proposed { proposal_index: PropIndex, deposit: Balance },
+	/// A referendum was launched.
+	Started { ref_index: ReferendumIndex },
+	/// A referendum passed.
+	Passed { ref_index: ReferendumIndex },
+	/// A referendum did not pass.
+	NotPassed { ref_index: ReferendumIndex },
+	/// The proposal of a referendum which passed was dispatched, with the given `result`.
+	Executed { ref_index: ReferendumIndex, result: DispatchResult },
+}
+
+/// This is the Democracy Module.
+/// It lets token holders propose calls, and vote on them in referenda. Calls which pass are
+/// dispatched from the root account.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of proposals which have been made, used as the index of the next proposal.
+	public_prop_count: PropIndex,
+	/// The proposals waiting to be launched.
+	public_props: Vec<PublicProposalOf<T>>,
+	/// The number of referenda which have been launched, used as the index of the next one.
+	referendum_count: ReferendumIndex,
+	/// A map from a referendum index to the referendum, until it ends.
+	referenda: BTreeMap<ReferendumIndex, ReferendumOf<T>>,
+	/// A map from an account to the balance locked by its votes, and the block it is locked until.
+	locks: BTreeMap<T::AccountID, VoteLockOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Democracy Module.
+	pub fn new() -> Self {
+		Self {
+			public_prop_count: 0,
+			public_props: Vec::new(),
+			referendum_count: 0,
+			referenda: BTreeMap::new(),
+			locks: BTreeMap::new(),
+		}
+	}
+
+	/// Get the proposals waiting to be launched.
+	pub fn public_props(&self) -> &[PublicProposalOf<T>] {
+		&self.public_props
+	}
+
+	/// Get a referendum which is being voted on.
+	pub fn referendum(&self, ref_index: ReferendumIndex) -> Option<&ReferendumOf<T>> {
+		self.referenda.get(&ref_index)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Launch the proposal with the largest deposit as a referendum, returning its deposit.
+	fn launch_public(runtime: &mut T, now: T::BlockNumber) -> DispatchResult {
+		let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let best = democracy
+			.public_props
+			.iter()
+			.enumerate()
+			// The earliest proposal wins a tie.
+			.max_by(|(i, a), (j, b)| a.deposit.cmp(&b.deposit).then(j.cmp(i)))
+			.map(|(i, _)| i);
+		let proposal = match best {
+			Some(i) => democracy.public_props.remove(i),
+			None => return Ok(()),
+		};
+
+		let ref_index = democracy.referendum_count;
+		democracy.referendum_count = ref_index.checked_add(1).ok_or("Overflow error")?;
+		let end = now.checked_add(&T::VOTING_PERIOD).ok_or("Overflow error")?;
+		let referendum = Referendum {
+			end,
+			proposal: proposal.proposal,
+			tally: Tally { ayes: T::Balance::zero(), nays: T::Balance::zero() },
+			votes: BTreeMap::new(),
+		};
+		democracy.referenda.insert(ref_index, referendum);
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.unreserve(&proposal.proposer, proposal.deposit);
+		Self::deposit_event(runtime, Event::Started { ref_index });
+		Ok(())
+	}
+
+	/// Tally the referenda which end at block `now`, and dispatch the proposals which passed.
+	fn bake_referenda(runtime: &mut T, now: T::BlockNumber) {
+		let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let ended = democracy
+			.referenda
+			.iter()
+			.filter(|(_, referendum)| referendum.end == now)
+			.map(|(ref_index, _)| *ref_index)
+			.collect::<Vec<_>>();
+
+		for ref_index in ended {
+			let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+			let referendum = match democracy.referenda.remove(&ref_index) {
+				Some(referendum) => referendum,
+				None => continue,
+			};
+			if referendum.tally.ayes > referendum.tally.nays {
+				Self::deposit_event(runtime, Event::Passed { ref_index });
+				let result = runtime.dispatch(support::root_account(), referendum.proposal);
+				Self::deposit_event(runtime, Event::Executed { ref_index, result });
+			} else {
+				Self::deposit_event(runtime, Event::NotPassed { ref_index });
+			}
+		}
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// End the referenda which are over, and launch a new referendum every `LAUNCH_PERIOD` blocks.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		Self::bake_referenda(runtime, n);
+		if n.checked_rem(&T::LAUNCH_PERIOD).is_some_and(|rem| rem.is_zero()) {
+			Self::launch_public(runtime, n)?;
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose the `proposal` call, reserving `value` from the `caller` as a deposit.
+	/// Every `LAUNCH_PERIOD` blocks, the proposal with the largest deposit becomes a referendum.
+	pub fn propose(
+		runtime: &mut T,
+		caller: T::AccountID,
+		proposal: Box<T::RuntimeCall>,
+		value: T::Balance,
+	) -> DispatchResult {
+		if value < T::MINIMUM_DEPOSIT {
+			return Err("Deposit is too low");
+		}
+		let democracy = GetPallet::<Pallet<T>>::pallet(runtime);
+		if democracy.public_props.len() >= T::MAX_PROPOSALS {
+			return Err("Too many proposals");
+		}
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, value)?;
+
+		let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let index = democracy.public_prop_count;
+		democracy.public_prop_count = index.checked_add(1).ok_or("Overflow error")?;
+		let proposal =
+			PublicProposal { index, proposer: caller, deposit: value, proposal: *proposal };
+		democracy.public_props.push(proposal);
+
+		Self::deposit_event(runtime, Event::Proposed { proposal_index: index, deposit: value });
+		Ok(())
+	}
+
+	/// Vote on the referendum at `ref_index`, replacing any previous vote of the `caller` on it.
+	/// The balance used to vote is locked until the end of the referendum, and then for as many
+	/// `VOTE_LOCKING_PERIOD`s as the conviction of the vote requires.
+	pub fn vote(
+		runtime: &mut T,
+		caller: T::AccountID,
+		ref_index: ReferendumIndex,
+		vote: Vote<T::Balance>,
+	) -> DispatchResult {
+		let balances = GetPallet::<balances::Pallet<T>>::pallet(runtime);
+		// Other locks overlap with the democracy lock, so only the total balance matters.
+		if vote.balance > balances.balance(&caller) {
+			return Err("Not enough funds");
+		}
+
+		let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let referendum = democracy.referenda.get_mut(&ref_index).ok_or("Referendum not found")?;
+		let periods = T::BlockNumber::from(vote.conviction.lock_periods());
+		let until = T::VOTE_LOCKING_PERIOD
+			.checked_mul(&periods)
+			.and_then(|duration| referendum.end.checked_add(&duration))
+			.ok_or("Overflow error")?;
+
+		let mut tally = referendum.tally;
+		if let Some(old) = referendum.votes.get(&caller) {
+			let votes = old.conviction.votes(old.balance);
+			let side = if old.aye { &mut tally.ayes } else { &mut tally.nays };
+			*side = side.checked_sub(&votes).ok_or("Overflow error")?;
+		}
+		let votes = vote.conviction.votes(vote.balance);
+		let side = if vote.aye { &mut tally.ayes } else { &mut tally.nays };
+		*side = side.checked_add(&votes).ok_or("Overflow error")?;
+		referendum.tally = tally;
+		referendum.votes.insert(caller.clone(), vote);
+
+		// The lock covers the largest balance used to vote, until the latest unlock.
+		let (amount, locked_until) = match democracy.locks.get(&caller) {
+			Some((amount, locked_until)) => (vote.balance.max(*amount), until.max(*locked_until)),
+			None => (vote.balance, until),
+		};
+		democracy.locks.insert(caller.clone(), (amount, locked_until));
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.set_lock(DEMOCRACY_ID, &caller, amount);
+		Ok(())
+	}
+
+	/// Remove the lock on the balance the `caller` used to vote, once it has expired.
+	pub fn unlock(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let democracy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let (_, until) = democracy.locks.get(&caller).ok_or("No lock to remove")?;
+		if *until > now {
+			return Err("Vote is still locked");
+		}
+		democracy.locks.remove(&caller);
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).remove_lock(DEMOCRACY_ID, &caller);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Conviction, Event, Tally, Vote};
+	use crate::{new_test_runtime, run_to_block, treasury, Runtime, RuntimeCall, RuntimeEvent};
+
+	type Democracy = super::Pallet<Runtime>;
+
+	fn approve_spend(proposal_index: u32) -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::treasury(treasury::Call::approve_proposal { proposal_index }))
+	}
+
+	fn vote(aye: bool, balance: u128, conviction: Conviction) -> Vote<u128> {
+		Vote { aye, balance, conviction }
+	}
+
+	fn has_event(runtime: &Runtime, event: Event<u128>) -> bool {
+		runtime.system.events().contains(&RuntimeEvent::democracy(event))
+	}
+
+	#[test]
+	fn conviction_votes() {
+		assert_eq!(Conviction::None.votes(100u128), 10);
+		assert_eq!(Conviction::Locked1x.votes(100u128), 100);
+		assert_eq!(Conviction::Locked6x.votes(100u128), 600);
+		assert_eq!(Conviction::Locked3x.lock_periods(), 4);
+	}
+
+	#[test]
+	fn proposal_with_largest_deposit_is_launched() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100), ("charlie", 100)]);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		let result = Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 5);
+		assert_eq!(result, Err("Deposit is too low"));
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		assert_eq!(Democracy::propose(&mut runtime, bob.clone(), approve_spend(1), 20), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 20);
+
+		run_to_block(&mut runtime, 10);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(runtime.democracy.public_props().len(), 1);
+		let referendum = runtime.democracy.referendum(0).unwrap();
+		assert_eq!(referendum.proposal, *approve_spend(1));
+		assert_eq!(referendum.end, 15);
+		assert!(has_event(&runtime, Event::Started { ref_index: 0 }));
+	}
+
+	#[test]
+	fn passed_referendum_is_dispatched_as_root() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100), ("charlie", 100)]);
+		let (alice, bob, charlie) = ("alice".to_string(), "bob".to_string(), "charlie".to_string());
+
+		let result = treasury::Pallet::propose_spend(&mut runtime, alice.clone(), 50, bob.clone());
+		assert_eq!(result, Ok(()));
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 10);
+
+		let aye = vote(true, 50, Conviction::Locked2x);
+		assert_eq!(Democracy::vote(&mut runtime, alice.clone(), 0, aye), Ok(()));
+		let nay = vote(false, 90, Conviction::Locked1x);
+		assert_eq!(Democracy::vote(&mut runtime, bob.clone(), 0, nay), Ok(()));
+		let aye = vote(true, 100, Conviction::None);
+		assert_eq!(Democracy::vote(&mut runtime, charlie.clone(), 0, aye), Ok(()));
+		assert_eq!(runtime.democracy.referendum(0).unwrap().tally, Tally { ayes: 110, nays: 90 });
+
+		// Bob changes his mind.
+		let aye = vote(true, 90, Conviction::Locked1x);
+		assert_eq!(Democracy::vote(&mut runtime, bob.clone(), 0, aye), Ok(()));
+		assert_eq!(runtime.democracy.referendum(0).unwrap().tally, Tally { ayes: 200, nays: 0 });
+
+		run_to_block(&mut runtime, 15);
+		assert!(runtime.democracy.referendum(0).is_none());
+		assert_eq!(runtime.treasury.approvals(), &[0]);
+		assert!(has_event(&runtime, Event::Executed { ref_index: 0, result: Ok(()) }));
+	}
+
+	#[test]
+	fn votes_are_locked() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100), ("charlie", 100)]);
+		let alice = "alice".to_string();
+
+		assert_eq!(Democracy::propose(&mut runtime, alice.clone(), approve_spend(0), 10), Ok(()));
+		run_to_block(&mut runtime, 10);
+		let nay = vote(false, 200, Conviction::None);
+		assert_eq!(Democracy::vote(&mut runtime, alice.clone(), 0, nay), Err("Not enough funds"));
+		let nay = vote(false, 60, Conviction::Locked2x);
+		assert_eq!(Democracy::vote(&mut runtime, alice.clone(), 0, nay), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 60);
+
+		// The referendum ends at block 15, and the vote is locked for two more periods.
+		run_to_block(&mut runtime, 15);
+		assert!(has_event(&runtime, Event::NotPassed { ref_index: 0 }));
+		run_to_block(&mut runtime, 24);
+		assert_eq!(Democracy::unlock(&mut runtime, alice.clone()), Err("Vote is still locked"));
+		run_to_block(&mut runtime, 25);
+		assert_eq!(Democracy::unlock(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 0);
+		assert_eq!(Democracy::unlock(&mut runtime, alice), Err("No lock to remove"));
+	}
+}
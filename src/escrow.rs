@@ -0,0 +1,273 @@
+use crate::{
+	balances::{self, BalanceStatus},
+	support::{self, DispatchResult, GetPallet},
+	system,
+};
+use num::Zero;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>>
+	+ balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The maximum number of escrows which can have their deadline at the same block.
+	const MAX_DEADLINES_PER_BLOCK: usize;
+}
+
+/// The index of an escrow.
+pub type EscrowIndex = u32;
+
+/// Funds of a payer held for a payee, until they are released or the deadline passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escrow<AccountID, Balance, BlockNumber> {
+	/// The account which locked the funds, and gets them back if they are not released.
+	pub payer: AccountID,
+	/// The account which gets the funds once they are released.
+	pub payee: AccountID,
+	/// The account which can release or refund the funds on behalf of both parties.
+	pub arbiter: AccountID,
+	/// The amount reserved from the payer.
+	pub amount: Balance,
+	/// The block at which the funds are refunded to the payer, if they were not released.
+	pub deadline: BlockNumber,
+}
+
+/// The escrow type used by a runtime `T`.
+pub type EscrowOf<T> = Escrow<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// A payer locked `amount` for a payee.
+	Created { escrow_index: EscrowIndex, payer: AccountID, payee: AccountID, amount: Balance },
+	/// The funds of an escrow were given to the payee.
+	Released { escrow_index: EscrowIndex },
+	/// The funds of an escrow were given back to the payer.
+	Refunded { escrow_index: EscrowIndex },
+}
+
+/// This is the Escrow Module.
+/// It lets a payer lock funds for a payee, which are released by the payer or an arbiter, or
+/// refunded to the payer once a deadline passes.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of escrows which have been created.
+	escrow_count: EscrowIndex,
+	/// A map from an escrow index to the escrow, while its funds are held.
+	escrows: BTreeMap<EscrowIndex, EscrowOf<T>>,
+	/// A map from a block to the escrows which have their deadline at that block.
+	deadlines: BTreeMap<T::BlockNumber, Vec<EscrowIndex>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Escrow Module.
+	pub fn new() -> Self {
+		Self { escrow_count: 0, escrows: BTreeMap::new(), deadlines: BTreeMap::new() }
+	}
+
+	/// Get an escrow whose funds are held.
+	pub fn escrow(&self, escrow_index: EscrowIndex) -> Option<&EscrowOf<T>> {
+		self.escrows.get(&escrow_index)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Remove an escrow, and its entry in the deadlines.
+	fn take_escrow(&mut self, escrow_index: EscrowIndex) -> Option<EscrowOf<T>> {
+		let escrow = self.escrows.remove(&escrow_index)?;
+		if let Some(indices) = self.deadlines.get_mut(&escrow.deadline) {
+			indices.retain(|index| *index != escrow_index);
+			if indices.is_empty() {
+				self.deadlines.remove(&escrow.deadline);
+			}
+		}
+		Some(escrow)
+	}
+
+	/// Give the funds of `escrow` back to the payer.
+	fn do_refund(runtime: &mut T, escrow_index: EscrowIndex, escrow: EscrowOf<T>) {
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.unreserve(&escrow.payer, escrow.amount);
+		Self::deposit_event(runtime, Event::Refunded { escrow_index });
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Refund all escrows which have their deadline at block `n`.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		let escrow = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let indices = escrow.deadlines.remove(&n).unwrap_or_default();
+		for escrow_index in indices {
+			let escrow = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+			if let Some(escrow) = escrow.escrows.remove(&escrow_index) {
+				Self::do_refund(runtime, escrow_index, escrow);
+			}
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Lock `amount` of the funds of the `caller` for `payee`. The funds are released by the
+	/// `caller` or the `arbiter`, and are refunded to the `caller` at the `deadline` block if they
+	/// were not released before.
+	pub fn create(
+		runtime: &mut T,
+		caller: T::AccountID,
+		payee: T::AccountID,
+		arbiter: T::AccountID,
+		amount: T::Balance,
+		deadline: T::BlockNumber,
+	) -> DispatchResult {
+		if amount.is_zero() {
+			return Err("Amount is zero");
+		}
+		if deadline <= GetPallet::<system::Pallet<T>>::pallet(runtime).block_number() {
+			return Err("Deadline is in the past");
+		}
+		let escrow = GetPallet::<Pallet<T>>::pallet(runtime);
+		let deadlines = escrow.deadlines.get(&deadline).map(Vec::len).unwrap_or_default();
+		if deadlines >= T::MAX_DEADLINES_PER_BLOCK {
+			return Err("Too many deadlines in the block");
+		}
+		let escrow_index = escrow.escrow_count;
+		let escrow_count = escrow_index.checked_add(1).ok_or("Escrow count overflow")?;
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, amount)?;
+		let escrow = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		escrow.escrow_count = escrow_count;
+		escrow.deadlines.entry(deadline).or_default().push(escrow_index);
+		let new_escrow =
+			Escrow { payer: caller.clone(), payee: payee.clone(), arbiter, amount, deadline };
+		escrow.escrows.insert(escrow_index, new_escrow);
+
+		let event = Event::Created { escrow_index, payer: caller, payee, amount };
+		Self::deposit_event(runtime, event);
+		Ok(())
+	}
+
+	/// Give the funds of an escrow to its payee.
+	/// Only the payer or the arbiter of the escrow can release it.
+	pub fn release(
+		runtime: &mut T,
+		caller: T::AccountID,
+		escrow_index: EscrowIndex,
+	) -> DispatchResult {
+		let escrow = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let details = escrow.escrows.get(&escrow_index).ok_or("Escrow not found")?;
+		if caller != details.payer && caller != details.arbiter {
+			return Err("Not allowed");
+		}
+		let details = escrow.take_escrow(escrow_index).ok_or("Escrow not found")?;
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		let (payer, payee) = (&details.payer, &details.payee);
+		balances.repatriate_reserved(payer, payee, details.amount, BalanceStatus::Free);
+		Self::deposit_event(runtime, Event::Released { escrow_index });
+		Ok(())
+	}
+
+	/// Give the funds of an escrow back to its payer before the deadline.
+	/// Only the payee or the arbiter of the escrow can refund it.
+	pub fn refund(
+		runtime: &mut T,
+		caller: T::AccountID,
+		escrow_index: EscrowIndex,
+	) -> DispatchResult {
+		let escrow = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let details = escrow.escrows.get(&escrow_index).ok_or("Escrow not found")?;
+		if caller != details.payee && caller != details.arbiter {
+			return Err("Not allowed");
+		}
+		let details = escrow.take_escrow(escrow_index).ok_or("Escrow not found")?;
+		Self::do_refund(runtime, escrow_index, details);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{new_test_runtime, run_to_block, Runtime, RuntimeEvent};
+
+	type Escrow = super::Pallet<Runtime>;
+
+	#[test]
+	fn create_escrow() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let result =
+			Escrow::create(&mut runtime, "alice".into(), "bob".into(), "charlie".into(), 60, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		assert_eq!(runtime.balances.balance(&alice), 40);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 60);
+		assert_eq!(runtime.escrow.escrow(0).unwrap().payee, "bob");
+
+		assert_eq!(
+			Escrow::create(&mut runtime, alice.clone(), "bob".into(), "charlie".into(), 0, 10),
+			Err("Amount is zero")
+		);
+		assert_eq!(
+			Escrow::create(&mut runtime, alice.clone(), "bob".into(), "charlie".into(), 50, 10),
+			Err("Not enough funds")
+		);
+		run_to_block(&mut runtime, 3);
+		assert_eq!(
+			Escrow::create(&mut runtime, alice.clone(), "bob".into(), "charlie".into(), 10, 3),
+			Err("Deadline is in the past")
+		);
+	}
+
+	#[test]
+	fn release_and_refund() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let result =
+			Escrow::create(&mut runtime, "alice".into(), "bob".into(), "charlie".into(), 60, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(Escrow::release(&mut runtime, bob.clone(), 0), Err("Not allowed"));
+		assert_eq!(Escrow::release(&mut runtime, "charlie".into(), 0), Ok(()));
+		assert_eq!(Escrow::release(&mut runtime, alice.clone(), 0), Err("Escrow not found"));
+		assert_eq!(runtime.balances.balance(&bob), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+
+		let result = Escrow::create(&mut runtime, alice.clone(), bob.clone(), bob.clone(), 40, 10);
+		assert_eq!(result, Ok(()));
+		assert_eq!(Escrow::refund(&mut runtime, alice.clone(), 1), Err("Not allowed"));
+		assert_eq!(Escrow::refund(&mut runtime, bob.clone(), 1), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 40);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+
+	#[test]
+	fn refund_after_deadline() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let result =
+			Escrow::create(&mut runtime, "alice".into(), "bob".into(), "charlie".into(), 60, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+
+		run_to_block(&mut runtime, 9);
+		assert!(runtime.escrow.escrow(0).is_some());
+		run_to_block(&mut runtime, 10);
+		assert!(runtime.escrow.escrow(0).is_none());
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert!(runtime
+			.system
+			.events()
+			.contains(&RuntimeEvent::escrow(Event::Refunded { escrow_index: 0 })));
+		assert_eq!(Escrow::release(&mut runtime, alice, 0), Err("Escrow not found"));
+	}
+}
@@ -0,0 +1,132 @@
+use crate::{
+	balances,
+	support::{self, DispatchResult, GetPallet},
+	system,
+};
+use num::{traits::CheckedAdd, Zero};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>>
+	+ balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The amount given to an account by every drip.
+	const DRIP_AMOUNT: Self::Balance;
+	/// The number of blocks an account must wait between two drips.
+	const DRIP_INTERVAL: Self::BlockNumber;
+	/// The maximum total amount the faucet can ever give out.
+	const MAX_ISSUANCE: Self::Balance;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// An account received `amount` from the faucet.
+	Dripped { who: AccountID, amount: Balance },
+}
+
+/// This is the Faucet Module.
+/// It lets any account create funds for itself, at most once every `DRIP_INTERVAL` blocks, until
+/// the faucet has given out `MAX_ISSUANCE` in total.
+///
+/// Anyone can mint funds with it, so it must only be used on development and test chains.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The total amount given out by the faucet.
+	issued: T::Balance,
+	/// A map from an account to the block of its last drip.
+	last_drip: BTreeMap<T::AccountID, T::BlockNumber>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Faucet Module.
+	pub fn new() -> Self {
+		Self { issued: T::Balance::zero(), last_drip: BTreeMap::new() }
+	}
+
+	/// Get the total amount given out by the faucet.
+	pub fn issued(&self) -> T::Balance {
+		self.issued
+	}
+
+	/// Get the block of the last drip of `who`, if any.
+	pub fn last_drip(&self, who: &T::AccountID) -> Option<T::BlockNumber> {
+		self.last_drip.get(who).copied()
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Give the `DRIP_AMOUNT` to the `caller`, creating those funds.
+	pub fn drip(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let faucet = GetPallet::<Pallet<T>>::pallet(runtime);
+		if let Some(last_drip) = faucet.last_drip(&caller) {
+			let next_drip =
+				last_drip.checked_add(&T::DRIP_INTERVAL).ok_or("Block number overflow")?;
+			if now < next_drip {
+				return Err("Drip too soon");
+			}
+		}
+		let amount = T::DRIP_AMOUNT;
+		let issued = faucet.issued.checked_add(&amount).ok_or("Balance overflow error")?;
+		if issued > T::MAX_ISSUANCE {
+			return Err("Faucet exhausted");
+		}
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).deposit(&caller, amount)?;
+		let faucet = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		faucet.issued = issued;
+		faucet.last_drip.insert(caller.clone(), now);
+		Self::deposit_event(runtime, Event::Dripped { who: caller, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{run_to_block, Runtime};
+
+	type Faucet = super::Pallet<Runtime>;
+
+	#[test]
+	fn drip_once_per_interval() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		run_to_block(&mut runtime, 1);
+
+		assert_eq!(Faucet::drip(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.faucet.last_drip(&alice), Some(1));
+		assert_eq!(Faucet::drip(&mut runtime, alice.clone()), Err("Drip too soon"));
+		run_to_block(&mut runtime, 10);
+		assert_eq!(Faucet::drip(&mut runtime, alice.clone()), Err("Drip too soon"));
+
+		// Other accounts have their own interval.
+		assert_eq!(Faucet::drip(&mut runtime, "bob".into()), Ok(()));
+		run_to_block(&mut runtime, 11);
+		assert_eq!(Faucet::drip(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 200);
+		assert_eq!(runtime.faucet.issued(), 300);
+	}
+
+	#[test]
+	fn issuance_is_capped() {
+		let mut runtime = Runtime::new();
+		for i in 0..10 {
+			assert_eq!(Faucet::drip(&mut runtime, i.to_string()), Ok(()));
+		}
+		assert_eq!(runtime.faucet.issued(), 1_000);
+		assert_eq!(Faucet::drip(&mut runtime, "alice".into()), Err("Faucet exhausted"));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 0);
+	}
+}
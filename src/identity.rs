@@ -0,0 +1,465 @@
+use crate::{
+	balances,
+	support::{self, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use num::{
+	traits::{CheckedMul, CheckedSub},
+	Zero,
+};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID>>>
+	+ balances::Config<Balance: CheckedMul + From<u32>>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can add registrars.
+	type RegistrarOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The deposit reserved for an identity.
+	const BASIC_DEPOSIT: Self::Balance;
+	/// The deposit reserved for each sub-account.
+	const SUB_ACCOUNT_DEPOSIT: Self::Balance;
+	/// The maximum length of each field of an identity, and of the name of a sub-account.
+	const MAX_FIELD_LENGTH: usize;
+	/// The maximum number of sub-accounts of an account.
+	const MAX_SUB_ACCOUNTS: usize;
+	/// The maximum number of registrars.
+	const MAX_REGISTRARS: usize;
+}
+
+/// The index of a registrar.
+pub type RegistrarIndex = u32;
+
+/// The information an account gives about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdentityInfo {
+	/// The name the account should be displayed with.
+	pub display: String,
+	/// The email address of the account holder, if any.
+	pub email: Option<String>,
+	/// The website of the account holder, if any.
+	pub web: Option<String>,
+}
+
+impl IdentityInfo {
+	/// Check that none of the fields is longer than `max_length`.
+	fn validate(&self, max_length: usize) -> DispatchResult {
+		let fields = [Some(&self.display), self.email.as_ref(), self.web.as_ref()];
+		if fields.into_iter().flatten().any(|field| field.len() > max_length) {
+			return Err("Field is too long");
+		}
+		Ok(())
+	}
+}
+
+/// A judgement of a registrar on the identity of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Judgement {
+	/// The registrar could not tell whether the identity is correct.
+	Unknown,
+	/// The identity seems correct, but it was not checked thoroughly.
+	Reasonable,
+	/// The identity was checked, and is correct.
+	KnownGood,
+	/// The identity used to be correct, but is now out of date.
+	OutOfDate,
+	/// The identity is of low quality, or misleading.
+	LowQuality,
+	/// The identity is wrong.
+	Erroneous,
+}
+
+/// The identity of an account, with the deposit reserved for it and the judgements of the
+/// registrars on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration<Balance> {
+	/// The information about the account.
+	pub info: IdentityInfo,
+	/// The amount reserved for the identity.
+	pub deposit: Balance,
+	/// The judgements of the registrars, sorted by registrar index.
+	pub judgements: Vec<(RegistrarIndex, Judgement)>,
+}
+
+/// The registration type used by a runtime `T`.
+pub type RegistrationOf<T> = Registration<<T as balances::Config>::Balance>;
+
+/// The sub-accounts of an account, and the deposit reserved for them.
+type SubsOf<T> = (<T as balances::Config>::Balance, Vec<<T as system::Config>::AccountID>);
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// A registrar was added.
+	RegistrarAdded { registrar_index: RegistrarIndex },
+	/// An account set its identity.
+	IdentitySet { who: AccountID },
+	/// An account cleared its identity.
+	IdentityCleared { who: AccountID },
+	/// A registrar gave a judgement on the identity of `target`.
+	JudgementGiven { target: AccountID, registrar_index: RegistrarIndex },
+	/// An account set its sub-accounts.
+	SubIdentitiesSet { main: AccountID, number_of_subs: u32 },
+}
+
+/// This is the Identity Module.
+/// It lets accounts attach information about themselves, which registrars can judge. Accounts can
+/// also name sub-accounts, which share their identity.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from an account to its identity.
+	identities: BTreeMap<T::AccountID, RegistrationOf<T>>,
+	/// The registrars, in order of their index.
+	registrars: Vec<T::AccountID>,
+	/// A map from a sub-account to its parent account, and its name.
+	super_of: BTreeMap<T::AccountID, (T::AccountID, String)>,
+	/// A map from an account to its sub-accounts, and the deposit reserved for them.
+	subs_of: BTreeMap<T::AccountID, SubsOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Identity Module.
+	pub fn new() -> Self {
+		Self {
+			identities: BTreeMap::new(),
+			registrars: Vec::new(),
+			super_of: BTreeMap::new(),
+			subs_of: BTreeMap::new(),
+		}
+	}
+
+	/// Get the identity of `who`.
+	pub fn identity(&self, who: &T::AccountID) -> Option<&RegistrationOf<T>> {
+		self.identities.get(who)
+	}
+
+	/// Get the parent account of the sub-account `who`, and its name.
+	pub fn super_of(&self, who: &T::AccountID) -> Option<&(T::AccountID, String)> {
+		self.super_of.get(who)
+	}
+
+	/// Get the sub-accounts of `who`.
+	pub fn subs_of(&self, who: &T::AccountID) -> &[T::AccountID] {
+		self.subs_of.get(who).map(|(_, subs)| subs.as_slice()).unwrap_or_default()
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Reserve or unreserve funds from `who`, so that `new` is reserved instead of `old`.
+	fn update_deposit(
+		runtime: &mut T,
+		who: &T::AccountID,
+		old: T::Balance,
+		new: T::Balance,
+	) -> DispatchResult {
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		if let Some(extra) = new.checked_sub(&old) {
+			balances.reserve(who, extra)?;
+		} else if let Some(excess) = old.checked_sub(&new) {
+			balances.unreserve(who, excess);
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Add `account` as a new registrar.
+	/// Only the `RegistrarOrigin` can add registrars.
+	pub fn add_registrar(
+		runtime: &mut T,
+		caller: T::AccountID,
+		account: T::AccountID,
+	) -> DispatchResult {
+		T::RegistrarOrigin::ensure_origin(runtime, &caller)?;
+		let identity = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if identity.registrars.contains(&account) {
+			return Err("Already a registrar");
+		}
+		if identity.registrars.len() >= T::MAX_REGISTRARS {
+			return Err("Too many registrars");
+		}
+		let registrar_index = identity.registrars.len() as RegistrarIndex;
+		identity.registrars.push(account);
+		Self::deposit_event(runtime, Event::RegistrarAdded { registrar_index });
+		Ok(())
+	}
+
+	/// Set the identity of the `caller` to `info`, reserving the `BASIC_DEPOSIT` if they had no
+	/// identity. Any judgements on a previous identity are removed.
+	pub fn set_identity(
+		runtime: &mut T,
+		caller: T::AccountID,
+		info: IdentityInfo,
+	) -> DispatchResult {
+		info.validate(T::MAX_FIELD_LENGTH)?;
+		let identity = GetPallet::<Pallet<T>>::pallet(runtime);
+		let deposit = match identity.identities.get(&caller) {
+			Some(registration) => registration.deposit,
+			None => {
+				let deposit = T::BASIC_DEPOSIT;
+				let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+				balances.reserve(&caller, deposit)?;
+				deposit
+			},
+		};
+
+		let registration = Registration { info, deposit, judgements: Vec::new() };
+		let identity = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		identity.identities.insert(caller.clone(), registration);
+		Self::deposit_event(runtime, Event::IdentitySet { who: caller });
+		Ok(())
+	}
+
+	/// Clear the identity of the `caller`, and remove its sub-accounts. All deposits are returned.
+	pub fn clear_identity(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let identity = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let registration = identity.identities.remove(&caller).ok_or("No identity")?;
+		let (subs_deposit, subs) =
+			identity.subs_of.remove(&caller).unwrap_or_else(|| (Zero::zero(), Vec::new()));
+		for sub in &subs {
+			identity.super_of.remove(sub);
+		}
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.unreserve(&caller, registration.deposit);
+		balances.unreserve(&caller, subs_deposit);
+		Self::deposit_event(runtime, Event::IdentityCleared { who: caller });
+		Ok(())
+	}
+
+	/// Give a `judgement` on the identity of `target`, as the registrar at `registrar_index`.
+	pub fn provide_judgement(
+		runtime: &mut T,
+		caller: T::AccountID,
+		registrar_index: RegistrarIndex,
+		target: T::AccountID,
+		judgement: Judgement,
+	) -> DispatchResult {
+		let identity = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if identity.registrars.get(registrar_index as usize) != Some(&caller) {
+			return Err("Not the registrar");
+		}
+		let registration = identity.identities.get_mut(&target).ok_or("No identity")?;
+		let judgements = &mut registration.judgements;
+		match judgements.binary_search_by_key(&registrar_index, |(index, _)| *index) {
+			Ok(position) => judgements[position].1 = judgement,
+			Err(position) => judgements.insert(position, (registrar_index, judgement)),
+		}
+
+		Self::deposit_event(runtime, Event::JudgementGiven { target, registrar_index });
+		Ok(())
+	}
+
+	/// Set the sub-accounts of the `caller`, with their names, replacing any previous ones. The
+	/// `caller` must have an identity, and the `SUB_ACCOUNT_DEPOSIT` is reserved for each one.
+	/// The sub-accounts are stored sorted, and must be distinct accounts other than the `caller`.
+	pub fn set_subs(
+		runtime: &mut T,
+		caller: T::AccountID,
+		mut subs: Vec<(T::AccountID, String)>,
+	) -> DispatchResult {
+		let identity = GetPallet::<Pallet<T>>::pallet(runtime);
+		if !identity.identities.contains_key(&caller) {
+			return Err("No identity");
+		}
+		if subs.len() > T::MAX_SUB_ACCOUNTS {
+			return Err("Too many sub-accounts");
+		}
+		subs.sort_by(|(a, _), (b, _)| a.cmp(b));
+		if subs.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+			return Err("Duplicate sub-account");
+		}
+		for (sub, name) in &subs {
+			if sub == &caller {
+				return Err("Cannot be own sub-account");
+			}
+			if name.len() > T::MAX_FIELD_LENGTH {
+				return Err("Field is too long");
+			}
+			if identity.super_of.get(sub).is_some_and(|(parent, _)| parent != &caller) {
+				return Err("Account is already a sub-account");
+			}
+		}
+
+		let (old_deposit, old_subs) = identity
+			.subs_of
+			.get(&caller)
+			.cloned()
+			.unwrap_or_else(|| (Zero::zero(), Vec::new()));
+		let new_deposit = T::SUB_ACCOUNT_DEPOSIT
+			.checked_mul(&T::Balance::from(subs.len() as u32))
+			.ok_or("Balance overflow error")?;
+		Self::update_deposit(runtime, &caller, old_deposit, new_deposit)?;
+
+		let identity = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		for sub in &old_subs {
+			identity.super_of.remove(sub);
+		}
+		let number_of_subs = subs.len() as u32;
+		let mut accounts = Vec::new();
+		for (sub, name) in subs {
+			identity.super_of.insert(sub.clone(), (caller.clone(), name));
+			accounts.push(sub);
+		}
+		if accounts.is_empty() {
+			identity.subs_of.remove(&caller);
+		} else {
+			identity.subs_of.insert(caller.clone(), (new_deposit, accounts));
+		}
+
+		Self::deposit_event(runtime, Event::SubIdentitiesSet { main: caller, number_of_subs });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{IdentityInfo, Judgement};
+	use crate::{new_test_runtime, support, Runtime};
+
+	type Identity = super::Pallet<Runtime>;
+
+	fn info(display: &str) -> IdentityInfo {
+		IdentityInfo {
+			display: display.to_string(),
+			email: Some("alice@example.com".to_string()),
+			web: None,
+		}
+	}
+
+	#[test]
+	fn set_and_clear_identity() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+
+		let long_name = "a".repeat(100);
+		let result = Identity::set_identity(&mut runtime, alice.clone(), info(&long_name));
+		assert_eq!(result, Err("Field is too long"));
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alice")), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+
+		// Updating the identity does not reserve another deposit.
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Al")), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(runtime.identity.identity(&alice).unwrap().info.display, "Al");
+
+		assert_eq!(Identity::clear_identity(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(Identity::clear_identity(&mut runtime, alice.clone()), Err("No identity"));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+	}
+
+	#[test]
+	fn registrar_judgements() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let root = support::root_account::<String>();
+
+		let result = Identity::add_registrar(&mut runtime, alice.clone(), bob.clone());
+		assert_eq!(result, Err("Bad origin"));
+		assert_eq!(Identity::add_registrar(&mut runtime, root.clone(), bob.clone()), Ok(()));
+		let result = Identity::add_registrar(&mut runtime, root, bob.clone());
+		assert_eq!(result, Err("Already a registrar"));
+
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				bob.clone(),
+				0,
+				alice.clone(),
+				Judgement::KnownGood
+			),
+			Err("No identity")
+		);
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alice")), Ok(()));
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				alice.clone(),
+				0,
+				alice.clone(),
+				Judgement::KnownGood
+			),
+			Err("Not the registrar")
+		);
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				bob.clone(),
+				0,
+				alice.clone(),
+				Judgement::Reasonable
+			),
+			Ok(())
+		);
+		assert_eq!(
+			Identity::provide_judgement(
+				&mut runtime,
+				bob.clone(),
+				0,
+				alice.clone(),
+				Judgement::KnownGood
+			),
+			Ok(())
+		);
+		let judgements = &runtime.identity.identity(&alice).unwrap().judgements;
+		assert_eq!(judgements, &vec![(0, Judgement::KnownGood)]);
+
+		// Changing the identity removes the judgements on it.
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Al")), Ok(()));
+		assert!(runtime.identity.identity(&alice).unwrap().judgements.is_empty());
+	}
+
+	#[test]
+	fn sub_accounts() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let subs = vec![
+			("bob".to_string(), "Bob".to_string()),
+			("charlie".to_string(), "Charlie".to_string()),
+		];
+
+		let result = Identity::set_subs(&mut runtime, alice.clone(), subs.clone());
+		assert_eq!(result, Err("No identity"));
+		assert_eq!(Identity::set_identity(&mut runtime, alice.clone(), info("Alice")), Ok(()));
+		assert_eq!(Identity::set_subs(&mut runtime, alice.clone(), subs), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 20);
+		assert_eq!(
+			runtime.identity.super_of(&"bob".to_string()),
+			Some(&(alice.clone(), "Bob".to_string()))
+		);
+
+		let subs = vec![("bob".to_string(), "Robert".to_string())];
+		assert_eq!(Identity::set_subs(&mut runtime, alice.clone(), subs), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 15);
+		assert_eq!(runtime.identity.subs_of(&alice), &["bob".to_string()]);
+		assert!(runtime.identity.super_of(&"charlie".to_string()).is_none());
+
+		let sub = |who: &str| (who.to_string(), who.to_uppercase());
+		let subs = vec![sub("bob"), sub("bob")];
+		let result = Identity::set_subs(&mut runtime, alice.clone(), subs);
+		assert_eq!(result, Err("Duplicate sub-account"));
+		let subs = vec![sub("bob"), sub("alice")];
+		let result = Identity::set_subs(&mut runtime, alice.clone(), subs);
+		assert_eq!(result, Err("Cannot be own sub-account"));
+		assert_eq!(runtime.identity.subs_of(&alice), &["bob".to_string()]);
+
+		// The sub-accounts are sorted.
+		let subs = vec![sub("dave"), sub("charlie")];
+		assert_eq!(Identity::set_subs(&mut runtime, alice.clone(), subs), Ok(()));
+		assert_eq!(runtime.identity.subs_of(&alice), &["charlie".to_string(), "dave".to_string()]);
+
+		assert_eq!(Identity::clear_identity(&mut runtime, alice.clone()), Ok(()));
+		assert!(runtime.identity.super_of(&"bob".to_string()).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+}
@@ -0,0 +1,240 @@
+use crate::{
+	balances::{self, BalanceStatus},
+	support::{self, DispatchResult, GetPallet, MultiAddress},
+	system,
+};
+use num::Zero;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID>>>
+	+ balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The deposit reserved for claiming an index.
+	const INDEX_DEPOSIT: Self::Balance;
+}
+
+/// A short index which refers to an account.
+pub type AccountIndex = u32;
+
+/// An index which was claimed by an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo<AccountID, Balance> {
+	/// The account the index refers to.
+	pub who: AccountID,
+	/// The deposit reserved from `who` for the index.
+	pub deposit: Balance,
+	/// Whether the index is frozen, in which case it can never be transferred or freed.
+	pub frozen: bool,
+}
+
+/// The index info type used by a runtime `T`.
+pub type IndexInfoOf<T> =
+	IndexInfo<<T as system::Config>::AccountID, <T as balances::Config>::Balance>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// An index was assigned to an account, by a claim or a transfer.
+	Assigned { who: AccountID, index: AccountIndex },
+	/// An index was freed, and can be claimed again.
+	Freed { index: AccountIndex },
+	/// An index was frozen to its account.
+	Frozen { index: AccountIndex, who: AccountID },
+}
+
+/// This is the Indices Module.
+/// It lets accounts claim short indices, which calls taking a `MultiAddress` accept in place of
+/// the full account. Claiming an index reserves a deposit, which is returned when it is freed.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from an index to the account which claimed it.
+	accounts: BTreeMap<AccountIndex, IndexInfoOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Indices Module.
+	pub fn new() -> Self {
+		Self { accounts: BTreeMap::new() }
+	}
+
+	/// Get the account `index` refers to.
+	pub fn lookup_index(&self, index: AccountIndex) -> Option<&T::AccountID> {
+		self.accounts.get(&index).map(|info| &info.who)
+	}
+
+	/// Get the info of `index`.
+	pub fn index_info(&self, index: AccountIndex) -> Option<&IndexInfoOf<T>> {
+		self.accounts.get(&index)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Get the info of `index`, checking that it is owned by `who` and not frozen.
+	fn owned_index(
+		&mut self,
+		who: &T::AccountID,
+		index: AccountIndex,
+	) -> Result<&mut IndexInfoOf<T>, &'static str> {
+		let info = self.accounts.get_mut(&index).ok_or("Index not claimed")?;
+		if &info.who != who {
+			return Err("Not the owner");
+		}
+		if info.frozen {
+			return Err("Index is frozen");
+		}
+		Ok(info)
+	}
+}
+
+/// Lets calls refer to an account by its full ID or by an index it claimed.
+impl<T: Config> support::Lookup<T> for Pallet<T> {
+	type Source = MultiAddress<T::AccountID, AccountIndex>;
+	type Target = T::AccountID;
+
+	fn lookup(runtime: &T, source: Self::Source) -> Result<T::AccountID, &'static str> {
+		match source {
+			MultiAddress::Id(who) => Ok(who),
+			MultiAddress::Index(index) => GetPallet::<Pallet<T>>::pallet(runtime)
+				.lookup_index(index)
+				.cloned()
+				.ok_or("Unknown index"),
+		}
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Claim `index` for the `caller`, reserving the `INDEX_DEPOSIT`.
+	pub fn claim(runtime: &mut T, caller: T::AccountID, index: AccountIndex) -> DispatchResult {
+		if GetPallet::<Pallet<T>>::pallet(runtime).accounts.contains_key(&index) {
+			return Err("Index already claimed");
+		}
+		let deposit = T::INDEX_DEPOSIT;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, deposit)?;
+
+		let info = IndexInfo { who: caller.clone(), deposit, frozen: false };
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).accounts.insert(index, info);
+		Self::deposit_event(runtime, Event::Assigned { who: caller, index });
+		Ok(())
+	}
+
+	/// Transfer `index` to `new`, along with its deposit, which stays reserved.
+	pub fn transfer(
+		runtime: &mut T,
+		caller: T::AccountID,
+		new: T::AccountID,
+		index: AccountIndex,
+	) -> DispatchResult {
+		if caller == new {
+			return Err("Index already owned");
+		}
+		let indices = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let deposit = indices.owned_index(&caller, index)?.deposit;
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.repatriate_reserved(&caller, &new, deposit, BalanceStatus::Reserved);
+
+		let indices = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		indices
+			.accounts
+			.insert(index, IndexInfo { who: new.clone(), deposit, frozen: false });
+		Self::deposit_event(runtime, Event::Assigned { who: new, index });
+		Ok(())
+	}
+
+	/// Free `index`, returning its deposit to the `caller`.
+	pub fn free(runtime: &mut T, caller: T::AccountID, index: AccountIndex) -> DispatchResult {
+		let indices = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let deposit = indices.owned_index(&caller, index)?.deposit;
+		indices.accounts.remove(&index);
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).unreserve(&caller, deposit);
+		Self::deposit_event(runtime, Event::Freed { index });
+		Ok(())
+	}
+
+	/// Freeze `index` to the `caller` forever. Its deposit is slashed.
+	pub fn freeze(runtime: &mut T, caller: T::AccountID, index: AccountIndex) -> DispatchResult {
+		let indices = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let info = indices.owned_index(&caller, index)?;
+		let deposit = core::mem::replace(&mut info.deposit, T::Balance::zero());
+		info.frozen = true;
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).slash_reserved(&caller, deposit);
+		Self::deposit_event(runtime, Event::Frozen { index, who: caller });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		balances, new_test_runtime,
+		support::{Dispatch, MultiAddress},
+		Runtime, RuntimeCall,
+	};
+
+	type Indices = super::Pallet<Runtime>;
+
+	#[test]
+	fn claim_and_free() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		assert_eq!(Indices::claim(&mut runtime, "alice".into(), 7), Ok(()));
+		let alice = "alice".to_string();
+		assert_eq!(runtime.indices.lookup_index(7), Some(&alice));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(Indices::claim(&mut runtime, "bob".into(), 7), Err("Index already claimed"));
+		assert_eq!(Indices::claim(&mut runtime, "charlie".into(), 8), Err("Not enough funds"));
+
+		assert_eq!(Indices::free(&mut runtime, "bob".into(), 7), Err("Not the owner"));
+		assert_eq!(Indices::free(&mut runtime, alice.clone(), 7), Ok(()));
+		assert_eq!(Indices::free(&mut runtime, alice.clone(), 7), Err("Index not claimed"));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(Indices::claim(&mut runtime, "bob".into(), 7), Ok(()));
+	}
+
+	#[test]
+	fn transfer_and_freeze() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		assert_eq!(Indices::claim(&mut runtime, "alice".into(), 7), Ok(()));
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// The deposit moves with the index, without touching the balance of the recipient.
+		assert_eq!(Indices::transfer(&mut runtime, alice.clone(), bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.indices.lookup_index(7), Some(&bob));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 10);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+
+		assert_eq!(Indices::freeze(&mut runtime, bob.clone(), 7), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(Indices::free(&mut runtime, bob.clone(), 7), Err("Index is frozen"));
+		let result = Indices::transfer(&mut runtime, bob.clone(), alice, 7);
+		assert_eq!(result, Err("Index is frozen"));
+		assert_eq!(runtime.indices.lookup_index(7), Some(&bob));
+	}
+
+	#[test]
+	fn transfer_balance_to_index() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		assert_eq!(Indices::claim(&mut runtime, "alice".into(), 7), Ok(()));
+		let transfer = |to| RuntimeCall::balances(balances::Call::transfer { to, amount: 30 });
+
+		let result = runtime.dispatch("bob".to_string(), transfer(MultiAddress::Index(8)));
+		assert_eq!(result, Err("Unknown index"));
+		let result = runtime.dispatch("bob".to_string(), transfer(MultiAddress::Index(7)));
+		assert_eq!(result, Ok(()));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 120);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 70);
+	}
+}
@@ -0,0 +1,236 @@
+use crate::{
+	balances,
+	support::{
+		self, BlockHash, DeriveAccount, DispatchResult, EnsureOrigin, GetPallet, Randomness,
+	},
+	system,
+};
+use num::{traits::CheckedAdd, Zero};
+
+pub trait Config:
+	system::Config<
+		AccountID: DeriveAccount,
+		RuntimeEvent: From<Event<Self::AccountID, Self::Balance, Self::BlockNumber>>,
+	> + balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The source of randomness used to draw the winner of a round.
+	type Randomness: Randomness<Self, BlockHash, Self::BlockNumber>;
+	/// The origin which can start a round.
+	type ManagerOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The maximum number of tickets which can be bought in a round.
+	const MAX_TICKETS: usize;
+}
+
+/// The index of a round of the lottery.
+pub type RoundIndex = u32;
+
+/// A round of the lottery which is in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round<Balance, BlockNumber> {
+	/// The index of the round.
+	pub index: RoundIndex,
+	/// The price of a ticket.
+	pub price: Balance,
+	/// The block at which the winner is drawn. Tickets can be bought until the block before.
+	pub end: BlockNumber,
+}
+
+/// The round type used by a runtime `T`.
+pub type RoundOf<T> = Round<<T as balances::Config>::Balance, <T as system::Config>::BlockNumber>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance, BlockNumber> {
+	/// A round started, with tickets sold at `price` until the `end` block.
+	Started { round_index: RoundIndex, price: Balance, end: BlockNumber },
+	/// An account bought a ticket.
+	TicketBought { round_index: RoundIndex, who: AccountID },
+	/// A round ended, and the pot was paid out to the winner. There is no winner if no tickets
+	/// were bought.
+	Drawn { round_index: RoundIndex, winner: Option<AccountID>, payout: Balance },
+}
+
+/// This is the Lottery Module.
+/// It lets a manager start rounds in which accounts buy tickets, paying into a pot. When a round
+/// ends, a winner is drawn at random and paid out the whole pot.
+///
+/// The winner is drawn with `T::Randomness`, which block authors may be able to bias, so the pot
+/// should stay small compared to what they could gain from it.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of rounds which have been started, used as the index of the next round.
+	round_count: RoundIndex,
+	/// The round in progress, if any.
+	round: Option<RoundOf<T>>,
+	/// The buyer of every ticket of the round in progress.
+	tickets: Vec<T::AccountID>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Lottery Module.
+	pub fn new() -> Self {
+		Self { round_count: 0, round: None, tickets: Vec::new() }
+	}
+
+	/// Get the account which holds the pot of the lottery.
+	pub fn account_id() -> T::AccountID {
+		T::AccountID::derive_account(&"lottery")
+	}
+
+	/// Get the round in progress, if any.
+	pub fn round(&self) -> Option<&RoundOf<T>> {
+		self.round.as_ref()
+	}
+
+	/// Get the buyer of every ticket of the round in progress.
+	pub fn tickets(&self) -> &[T::AccountID] {
+		&self.tickets
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance, T::BlockNumber>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// End the round in progress, paying out the whole pot to the buyer of a random ticket.
+	fn draw(runtime: &mut T, round_index: RoundIndex) -> DispatchResult {
+		let lottery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		lottery.round = None;
+		let tickets = core::mem::take(&mut lottery.tickets);
+		if tickets.is_empty() {
+			let event = Event::Drawn { round_index, winner: None, payout: T::Balance::zero() };
+			Self::deposit_event(runtime, event);
+			return Ok(());
+		}
+
+		let subject = [b"lottery".as_slice(), &round_index.to_le_bytes()].concat();
+		let (random, _) = T::Randomness::random(runtime, &subject);
+		let winner = tickets[(random % tickets.len() as u64) as usize].clone();
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		let payout = balances.balance(&Self::account_id());
+		balances.do_transfer(Self::account_id(), winner.clone(), payout)?;
+		Self::deposit_event(runtime, Event::Drawn { round_index, winner: Some(winner), payout });
+		Ok(())
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Draw the winner of the round in progress once its end block is reached.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		match GetPallet::<Pallet<T>>::pallet(runtime).round() {
+			Some(round) if n >= round.end => Self::draw(runtime, round.index),
+			_ => Ok(()),
+		}
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Start a round in which tickets are sold at `price`, for `length` blocks.
+	/// Only the `ManagerOrigin` can start a round, and only when no round is in progress.
+	pub fn start_lottery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		price: T::Balance,
+		length: T::BlockNumber,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		if length.is_zero() {
+			return Err("Round length is zero");
+		}
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let end = now.checked_add(&length).ok_or("Block number overflow")?;
+		let lottery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if lottery.round.is_some() {
+			return Err("Lottery already in progress");
+		}
+		let index = lottery.round_count;
+		lottery.round_count = index.checked_add(1).ok_or("Round count overflow")?;
+		lottery.round = Some(Round { index, price, end });
+
+		Self::deposit_event(runtime, Event::Started { round_index: index, price, end });
+		Ok(())
+	}
+
+	/// Buy a ticket for the round in progress, paying its price into the pot.
+	pub fn buy_ticket(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let lottery = GetPallet::<Pallet<T>>::pallet(runtime);
+		let round = lottery.round.clone().ok_or("Lottery not in progress")?;
+		if now >= round.end {
+			return Err("Lottery has ended");
+		}
+		if lottery.tickets.len() >= T::MAX_TICKETS {
+			return Err("Too many tickets");
+		}
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.do_transfer(caller.clone(), Self::account_id(), round.price)?;
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).tickets.push(caller.clone());
+		Self::deposit_event(runtime, Event::TicketBought { round_index: round.index, who: caller });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{new_test_runtime, run_to_block, support, Runtime, RuntimeEvent};
+
+	type Lottery = super::Pallet<Runtime>;
+
+	#[test]
+	fn start_lottery() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100), ("charlie", 100)]);
+		let result = Lottery::start_lottery(&mut runtime, support::root_account(), 10, 5);
+		assert_eq!(result, Ok(()));
+		let root = support::root_account::<String>();
+		assert_eq!(Lottery::start_lottery(&mut runtime, "alice".into(), 10, 5), Err("Bad origin"));
+		let result = Lottery::start_lottery(&mut runtime, root.clone(), 10, 5);
+		assert_eq!(result, Err("Lottery already in progress"));
+
+		// A round without tickets ends without a winner, and a new one can be started.
+		run_to_block(&mut runtime, 5);
+		assert!(runtime.lottery.round().is_none());
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::lottery(Event::Drawn { round_index: 0, winner: None, payout: 0 }))
+		);
+		let result = Lottery::start_lottery(&mut runtime, root.clone(), 20, 0);
+		assert_eq!(result, Err("Round length is zero"));
+		assert_eq!(Lottery::start_lottery(&mut runtime, root, 20, 3), Ok(()));
+		assert_eq!(runtime.lottery.round().map(|round| (round.index, round.end)), Some((1, 8)));
+	}
+
+	#[test]
+	fn buy_tickets_and_draw() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100), ("charlie", 100)]);
+		let result = Lottery::start_lottery(&mut runtime, support::root_account(), 10, 5);
+		assert_eq!(result, Ok(()));
+		let players = ["alice", "bob", "charlie"].map(String::from);
+
+		assert_eq!(Lottery::buy_ticket(&mut runtime, "dave".into()), Err("Not enough funds"));
+		for player in &players {
+			assert_eq!(Lottery::buy_ticket(&mut runtime, player.clone()), Ok(()));
+		}
+		assert_eq!(Lottery::buy_ticket(&mut runtime, "alice".into()), Ok(()));
+		assert_eq!(runtime.lottery.tickets().len(), 4);
+		assert_eq!(runtime.balances.balance(&Lottery::account_id()), 40);
+
+		run_to_block(&mut runtime, 5);
+		assert!(runtime.lottery.round().is_none());
+		assert!(runtime.lottery.tickets().is_empty());
+		assert_eq!(runtime.balances.balance(&Lottery::account_id()), 0);
+		let Some(RuntimeEvent::lottery(Event::Drawn { round_index: 0, winner, payout: 40 })) =
+			runtime.system.events().last().cloned()
+		else {
+			panic!("The round was not drawn");
+		};
+		let winner = winner.expect("There were tickets");
+		let expected = if winner == "alice" { 120 } else { 130 };
+		assert_eq!(runtime.balances.balance(&winner), expected);
+		assert_eq!(Lottery::buy_ticket(&mut runtime, winner), Err("Lottery not in progress"));
+	}
+}
@@ -1,7 +1,31 @@
+mod aura;
 mod balances;
+mod bounties;
+mod collective;
+mod crowdfund;
+mod democracy;
+mod escrow;
+mod faucet;
+mod identity;
+mod indices;
+mod lottery;
+mod membership;
+mod multisig;
+mod names;
+mod oracle;
+mod preimage;
 mod proof_of_existence;
+mod proxy;
+mod randomness;
+mod recovery;
+mod scheduler;
+mod staking;
 mod support;
 mod system;
+mod timestamp;
+mod treasury;
+mod utility;
+mod vesting;
 
 use support::Dispatch;
 
@@ -12,61 +36,412 @@ mod types {
 	pub type Balance = u128;
 	pub type BlockNumber = u32;
 	pub type Nonce = u32;
+	pub type Moment = u64;
 	pub type Extrinsic = support::Extrinsic<AccountID, crate::RuntimeCall>;
-	pub type Header = support::Header<BlockNumber>;
+	pub type Header = support::Header<BlockNumber, AccountID>;
 	pub type Block = support::Block<Header, Extrinsic>;
 	pub type Content = &'static str;
+	pub type OracleKey = &'static str;
+	pub type OracleValue = u128;
+}
+
+// These are all the events which can be emitted by the pallets in our Runtime.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeEvent {
+	utility(utility::Event),
+	scheduler(scheduler::Event<types::BlockNumber>),
+	treasury(treasury::Event<types::AccountID, types::Balance>),
+	staking(staking::Event<types::AccountID, types::Balance>),
+	democracy(democracy::Event<types::Balance>),
+	collective(collective::Event<types::AccountID>),
+	identity(identity::Event<types::AccountID>),
+	recovery(recovery::Event<types::AccountID>),
+	escrow(escrow::Event<types::AccountID, types::Balance>),
+	oracle(oracle::Event<types::AccountID, types::OracleKey, types::OracleValue>),
+	lottery(lottery::Event<types::AccountID, types::Balance, types::BlockNumber>),
+	names(names::Event<types::AccountID, types::BlockNumber>),
+	crowdfund(crowdfund::Event<types::AccountID, types::Balance>),
+	bounties(bounties::Event<types::AccountID, types::Balance>),
+	preimage(preimage::Event),
+	membership(membership::Event<types::AccountID>),
+	indices(indices::Event<types::AccountID>),
+	faucet(faucet::Event<types::AccountID, types::Balance>),
+	aura(aura::Event<types::AccountID>),
+}
+
+impl From<utility::Event> for RuntimeEvent {
+	fn from(event: utility::Event) -> Self {
+		Self::utility(event)
+	}
+}
+
+impl From<scheduler::Event<types::BlockNumber>> for RuntimeEvent {
+	fn from(event: scheduler::Event<types::BlockNumber>) -> Self {
+		Self::scheduler(event)
+	}
+}
+
+impl From<treasury::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: treasury::Event<types::AccountID, types::Balance>) -> Self {
+		Self::treasury(event)
+	}
+}
+
+impl From<staking::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: staking::Event<types::AccountID, types::Balance>) -> Self {
+		Self::staking(event)
+	}
+}
+
+impl From<democracy::Event<types::Balance>> for RuntimeEvent {
+	fn from(event: democracy::Event<types::Balance>) -> Self {
+		Self::democracy(event)
+	}
+}
+
+impl From<collective::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: collective::Event<types::AccountID>) -> Self {
+		Self::collective(event)
+	}
+}
+
+impl From<identity::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: identity::Event<types::AccountID>) -> Self {
+		Self::identity(event)
+	}
+}
+
+impl From<recovery::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: recovery::Event<types::AccountID>) -> Self {
+		Self::recovery(event)
+	}
+}
+
+impl From<escrow::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: escrow::Event<types::AccountID, types::Balance>) -> Self {
+		Self::escrow(event)
+	}
+}
+
+impl From<oracle::Event<types::AccountID, types::OracleKey, types::OracleValue>> for RuntimeEvent {
+	fn from(event: oracle::Event<types::AccountID, types::OracleKey, types::OracleValue>) -> Self {
+		Self::oracle(event)
+	}
+}
+
+impl From<lottery::Event<types::AccountID, types::Balance, types::BlockNumber>> for RuntimeEvent {
+	fn from(event: lottery::Event<types::AccountID, types::Balance, types::BlockNumber>) -> Self {
+		Self::lottery(event)
+	}
+}
+
+impl From<names::Event<types::AccountID, types::BlockNumber>> for RuntimeEvent {
+	fn from(event: names::Event<types::AccountID, types::BlockNumber>) -> Self {
+		Self::names(event)
+	}
+}
+
+impl From<crowdfund::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: crowdfund::Event<types::AccountID, types::Balance>) -> Self {
+		Self::crowdfund(event)
+	}
+}
+
+impl From<bounties::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: bounties::Event<types::AccountID, types::Balance>) -> Self {
+		Self::bounties(event)
+	}
+}
+
+impl From<preimage::Event> for RuntimeEvent {
+	fn from(event: preimage::Event) -> Self {
+		Self::preimage(event)
+	}
+}
+
+impl From<membership::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: membership::Event<types::AccountID>) -> Self {
+		Self::membership(event)
+	}
+}
+
+impl From<indices::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: indices::Event<types::AccountID>) -> Self {
+		Self::indices(event)
+	}
+}
+
+impl From<faucet::Event<types::AccountID, types::Balance>> for RuntimeEvent {
+	fn from(event: faucet::Event<types::AccountID, types::Balance>) -> Self {
+		Self::faucet(event)
+	}
+}
+
+impl From<aura::Event<types::AccountID>> for RuntimeEvent {
+	fn from(event: aura::Event<types::AccountID>) -> Self {
+		Self::aura(event)
+	}
 }
 
 // This is our main Runtime.
 // It accumulates all of the different pallets we want to use.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[macros::runtime]
 pub struct Runtime {
 	system: system::Pallet<Self>,
 	balances: balances::Pallet<Self>,
 	proof_of_existence: proof_of_existence::Pallet<Self>,
+	vesting: vesting::Pallet<Self>,
+	multisig: multisig::Pallet<Self>,
+	utility: utility::Pallet<Self>,
+	proxy: proxy::Pallet<Self>,
+	scheduler: scheduler::Pallet<Self>,
+	timestamp: timestamp::Pallet<Self>,
+	treasury: treasury::Pallet<Self>,
+	staking: staking::Pallet<Self>,
+	democracy: democracy::Pallet<Self>,
+	collective: collective::Pallet<Self>,
+	identity: identity::Pallet<Self>,
+	recovery: recovery::Pallet<Self>,
+	escrow: escrow::Pallet<Self>,
+	oracle: oracle::Pallet<Self>,
+	randomness: randomness::Pallet<Self>,
+	lottery: lottery::Pallet<Self>,
+	names: names::Pallet<Self>,
+	crowdfund: crowdfund::Pallet<Self>,
+	bounties: bounties::Pallet<Self>,
+	preimage: preimage::Pallet<Self>,
+	membership: membership::Pallet<Self>,
+	indices: indices::Pallet<Self>,
+	faucet: faucet::Pallet<Self>,
+	aura: aura::Pallet<Self>,
 }
 
 impl system::Config for Runtime {
 	type AccountID = types::AccountID;
 	type BlockNumber = types::BlockNumber;
 	type Nonce = types::Nonce;
+	type RuntimeEvent = RuntimeEvent;
 }
 
 impl balances::Config for Runtime {
 	type Balance = types::Balance;
+	type OnUnbalanced = treasury::Pallet<Self>;
+	type Lookup = indices::Pallet<Self>;
 }
 
 impl proof_of_existence::Config for Runtime {
 	type Content = types::Content;
 }
 
+impl vesting::Config for Runtime {
+	type BlockNumberToBalance = support::ConvertInto;
+	const MAX_VESTING_SCHEDULES: usize = 28;
+}
+
+impl multisig::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const MAX_SIGNATORIES: usize = 100;
+}
+
+impl utility::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+}
+
+// The kinds of proxies accounts can add in our Runtime, and the calls each of them can make.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProxyType {
+	/// A proxy which can make any call.
+	Any,
+	/// A proxy which can only transfer balances.
+	Transfer,
+	/// A proxy which can only manage proof of existence claims.
+	Claims,
+}
+
+impl support::InstanceFilter<RuntimeCall> for ProxyType {
+	fn filter(&self, call: &RuntimeCall) -> bool {
+		match self {
+			ProxyType::Any => true,
+			ProxyType::Transfer => {
+				matches!(call, RuntimeCall::balances(balances::Call::transfer { .. }))
+			},
+			ProxyType::Claims => matches!(call, RuntimeCall::proof_of_existence(_)),
+		}
+	}
+}
+
+impl proxy::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	type ProxyType = ProxyType;
+	const MAX_PROXIES: usize = 32;
+	const MAX_PENDING: usize = 32;
+}
+
+impl scheduler::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const MAX_SCHEDULED_PER_BLOCK: usize = 50;
+}
+
+impl timestamp::Config for Runtime {
+	type Moment = types::Moment;
+	const MINIMUM_PERIOD: types::Moment = 3_000;
+}
+
+impl treasury::Config for Runtime {
+	type ApproveOrigin =
+		support::EitherOf<support::EnsureRoot, collective::EnsureProportionAtLeast<Runtime, 1, 2>>;
+	const PROPOSAL_BOND: types::Balance = 10;
+	const SPEND_PERIOD: types::BlockNumber = 10;
+	const MAX_APPROVALS: usize = 100;
+}
+
+impl staking::Config for Runtime {
+	type SlashOrigin = support::EnsureRoot;
+	const ERA_LENGTH: types::BlockNumber = 5;
+	const BONDING_DURATION: staking::EraIndex = 2;
+	const VALIDATOR_COUNT: usize = 2;
+	const MAX_NOMINATIONS: usize = 16;
+	const MAX_UNLOCKING_CHUNKS: usize = 32;
+	const ERA_REWARD: types::Balance = 100;
+}
+
+impl democracy::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const LAUNCH_PERIOD: types::BlockNumber = 10;
+	const VOTING_PERIOD: types::BlockNumber = 5;
+	const VOTE_LOCKING_PERIOD: types::BlockNumber = 5;
+	const MINIMUM_DEPOSIT: types::Balance = 10;
+	const MAX_PROPOSALS: usize = 100;
+}
+
+impl collective::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const MOTION_DURATION: types::BlockNumber = 5;
+	const MAX_PROPOSALS: usize = 100;
+}
+
+impl identity::Config for Runtime {
+	type RegistrarOrigin = support::EnsureRoot;
+	const BASIC_DEPOSIT: types::Balance = 10;
+	const SUB_ACCOUNT_DEPOSIT: types::Balance = 5;
+	const MAX_FIELD_LENGTH: usize = 32;
+	const MAX_SUB_ACCOUNTS: usize = 100;
+	const MAX_REGISTRARS: usize = 20;
+}
+
+impl recovery::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const CONFIG_DEPOSIT: types::Balance = 10;
+	const RECOVERY_DEPOSIT: types::Balance = 20;
+	const MAX_FRIENDS: usize = 9;
+}
+
+impl escrow::Config for Runtime {
+	const MAX_DEADLINES_PER_BLOCK: usize = 50;
+}
+
+impl oracle::Config for Runtime {
+	type OracleKey = types::OracleKey;
+	type OracleValue = types::OracleValue;
+	type FeederOrigin = support::EnsureRoot;
+	const MAX_FEEDERS: usize = 20;
+}
+
+impl randomness::Config for Runtime {
+	const RANDOM_MATERIAL_LEN: u32 = 81;
+}
+
+impl lottery::Config for Runtime {
+	type Randomness = randomness::Pallet<Self>;
+	type ManagerOrigin = support::EnsureRoot;
+	const MAX_TICKETS: usize = 1_000;
+}
+
+impl names::Config for Runtime {
+	type BlockNumberToBalance = support::ConvertInto;
+	const RENT_PER_BLOCK: types::Balance = 1;
+	const MAX_REGISTRATION_LENGTH: types::BlockNumber = 500;
+	const MAX_NAME_LENGTH: usize = 32;
+}
+
+impl crowdfund::Config for Runtime {
+	const MIN_CONTRIBUTION: types::Balance = 5;
+}
+
+impl bounties::Config for Runtime {
+	type ApproveOrigin =
+		support::EitherOf<support::EnsureRoot, collective::EnsureProportionAtLeast<Runtime, 1, 2>>;
+	const BOUNTY_DEPOSIT: types::Balance = 10;
+	const CURATOR_DEPOSIT: types::Balance = 5;
+	const PAYOUT_DELAY: types::BlockNumber = 3;
+	const MAX_DESCRIPTION_LENGTH: usize = 256;
+}
+
+impl preimage::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	type ManagerOrigin = support::EnsureRoot;
+	const PREIMAGE_DEPOSIT: types::Balance = 10;
+}
+
+impl membership::Config for Runtime {
+	type ManagerOrigin = support::EnsureRoot;
+	type MembershipChanged = collective::Pallet<Runtime>;
+	const MAX_MEMBERS: usize = 100;
+}
+
+impl indices::Config for Runtime {
+	const INDEX_DEPOSIT: types::Balance = 10;
+}
+
+impl faucet::Config for Runtime {
+	const DRIP_AMOUNT: types::Balance = 100;
+	const DRIP_INTERVAL: types::BlockNumber = 10;
+	const MAX_ISSUANCE: types::Balance = 1_000;
+}
+
+impl aura::Config for Runtime {
+	type AuthorityOrigin = support::EnsureRoot;
+	const MAX_AUTHORITIES: usize = 32;
+}
+
 fn main() {
 	// instantiate the runtime
 	let mut runtime = Runtime::new();
 
-	// set alice's balance to 100 tokens
 	let alice = String::from("alice");
 	let bob = String::from("bob");
 	let charlie: String = String::from("charlie");
 
-	runtime.balances.set_balance(&alice, 100);
+	// alice and bob take turns authoring blocks
+	let authorities = vec![alice.clone(), bob.clone()];
+	aura::Pallet::set_authorities(&mut runtime, support::root_account(), authorities)
+		.expect("Invalid authorities");
 
 	let block_1 = types::Block {
-		header: types::Header { block_number: 1 },
+		header: types::Header { block_number: 1, author: bob.clone() },
 		extrinsics: vec![
 			types::Extrinsic {
-				caller: alice.clone(),
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now: 6_000 }),
+			},
+			// alice gets 100 tokens from the faucet
+			types::Extrinsic {
+				caller: Some(alice.clone()),
+				call: RuntimeCall::faucet(faucet::Call::drip {}),
+			},
+			types::Extrinsic {
+				caller: Some(alice.clone()),
 				call: RuntimeCall::balances(balances::Call::transfer {
-					to: bob.clone(),
+					to: bob.clone().into(),
 					amount: 50,
 				}),
 			},
 			types::Extrinsic {
-				caller: alice.clone(),
+				caller: Some(alice.clone()),
 				call: RuntimeCall::balances(balances::Call::transfer {
-					to: charlie.clone(),
+					to: charlie.clone().into(),
 					amount: 20,
 				}),
 			},
@@ -74,20 +449,31 @@ fn main() {
 	};
 
 	let block_2 = types::Block {
-		header: types::Header { block_number: 2 },
+		header: types::Header { block_number: 2, author: alice.clone() },
 		extrinsics: vec![
 			types::Extrinsic {
-				caller: charlie.clone(),
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now: 12_000 }),
+			},
+			types::Extrinsic {
+				caller: Some(charlie.clone()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
 					claim: "0xContent claim",
 				}),
 			},
 			types::Extrinsic {
-				caller: bob.clone(),
+				caller: Some(bob.clone()),
 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
 					claim: "0xContent claim",
 				}),
 			},
+			types::Extrinsic {
+				caller: Some(bob.clone()),
+				call: RuntimeCall::vesting(vesting::Call::vested_transfer {
+					target: charlie.clone(),
+					schedule: vesting::VestingInfo { locked: 20, per_block: 5, starting_block: 2 },
+				}),
+			},
 		],
 	};
 
@@ -97,10 +483,39 @@ fn main() {
 	// uncomment lines to cause panic.
 
 	// let block_3 = types::Block {
-	// 	header: types::Header{block_number: 2},
+	// 	header: types::Header{block_number: 2, author: bob.clone()},
 	// 	extrinsics: vec!{},
 	// };
 	// runtime.execute_block(block_3).expect("Invalid block");
 
 	println!("{:#?}", runtime);
 }
+
+// Create a runtime in which each of the `accounts` starts with the given balance.
+#[cfg(test)]
+fn new_test_runtime(accounts: &[(&str, types::Balance)]) -> Runtime {
+	let mut runtime = Runtime::new();
+	for (who, balance) in accounts {
+		runtime.balances.set_balance(&who.to_string(), *balance);
+	}
+	runtime
+}
+
+// Execute blocks which only contain the timestamp inherent, until block `n`. Every block is
+// authored by the expected authority, if any.
+#[cfg(test)]
+fn run_to_block(runtime: &mut Runtime, n: types::BlockNumber) {
+	while runtime.system.block_number() < n {
+		let block_number = runtime.system.block_number() + 1;
+		let now = block_number as types::Moment * 6_000;
+		let author = runtime.aura.expected_author(block_number).cloned().unwrap_or_default();
+		let block = types::Block {
+			header: types::Header { block_number, author },
+			extrinsics: vec![types::Extrinsic {
+				caller: None,
+				call: RuntimeCall::timestamp(timestamp::Call::set { now }),
+			}],
+		};
+		runtime.execute_block(block).expect("Invalid block");
+	}
+}
@@ -0,0 +1,251 @@
+use crate::{
+	support::{self, ChangeMembers, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use core::slice;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID>>>
+	+ Sized
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can add, remove, swap and reset the members.
+	type ManagerOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The pallet notified whenever the members change.
+	type MembershipChanged: ChangeMembers<Self, Self::AccountID>;
+	/// The maximum number of members.
+	const MAX_MEMBERS: usize;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// An account was added to the members.
+	MemberAdded { who: AccountID },
+	/// An account was removed from the members.
+	MemberRemoved { who: AccountID },
+	/// A member was replaced by another account.
+	MembersSwapped { remove: AccountID, add: AccountID },
+	/// The members were reset to a new set.
+	MembersReset { members: Vec<AccountID> },
+	/// A member changed its account.
+	KeyChanged { old: AccountID, new: AccountID },
+}
+
+/// This is the Membership Module.
+/// It maintains a sorted set of members on behalf of another pallet, like a collective, which is
+/// notified through `ChangeMembers` whenever the members change.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The members, sorted.
+	members: Vec<T::AccountID>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Membership Module.
+	pub fn new() -> Self {
+		Self { members: Vec::new() }
+	}
+
+	/// Get the members.
+	pub fn members(&self) -> &[T::AccountID] {
+		&self.members
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Notify `T::MembershipChanged` of a change, then store the sorted `new_members`.
+	fn change_members(
+		runtime: &mut T,
+		incoming: &[T::AccountID],
+		outgoing: &[T::AccountID],
+		new_members: Vec<T::AccountID>,
+	) {
+		T::MembershipChanged::change_members_sorted(runtime, incoming, outgoing, &new_members);
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).members = new_members;
+	}
+
+	/// Get the members with `remove` replaced by `add`, checking that `remove` is a member and
+	/// `add` is not.
+	fn swapped(
+		&self,
+		remove: &T::AccountID,
+		add: &T::AccountID,
+	) -> Result<Vec<T::AccountID>, &'static str> {
+		let mut members = self.members.clone();
+		let index = members.binary_search(remove).map_err(|_| "Not a member")?;
+		members.remove(index);
+		let index = members.binary_search(add).err().ok_or("Already a member")?;
+		members.insert(index, add.clone());
+		Ok(members)
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Add `who` to the members.
+	/// Only the `ManagerOrigin` can add members.
+	pub fn add_member(runtime: &mut T, caller: T::AccountID, who: T::AccountID) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		let mut members = GetPallet::<Pallet<T>>::pallet(runtime).members.clone();
+		let index = members.binary_search(&who).err().ok_or("Already a member")?;
+		if members.len() >= T::MAX_MEMBERS {
+			return Err("Too many members");
+		}
+		members.insert(index, who.clone());
+
+		Self::change_members(runtime, slice::from_ref(&who), &[], members);
+		Self::deposit_event(runtime, Event::MemberAdded { who });
+		Ok(())
+	}
+
+	/// Remove `who` from the members.
+	/// Only the `ManagerOrigin` can remove members.
+	pub fn remove_member(
+		runtime: &mut T,
+		caller: T::AccountID,
+		who: T::AccountID,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		let mut members = GetPallet::<Pallet<T>>::pallet(runtime).members.clone();
+		let index = members.binary_search(&who).map_err(|_| "Not a member")?;
+		members.remove(index);
+
+		Self::change_members(runtime, &[], slice::from_ref(&who), members);
+		Self::deposit_event(runtime, Event::MemberRemoved { who });
+		Ok(())
+	}
+
+	/// Replace the member `remove` with `add`.
+	/// Only the `ManagerOrigin` can swap members.
+	pub fn swap_member(
+		runtime: &mut T,
+		caller: T::AccountID,
+		remove: T::AccountID,
+		add: T::AccountID,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		let members = GetPallet::<Pallet<T>>::pallet(runtime).swapped(&remove, &add)?;
+
+		Self::change_members(runtime, slice::from_ref(&add), slice::from_ref(&remove), members);
+		Self::deposit_event(runtime, Event::MembersSwapped { remove, add });
+		Ok(())
+	}
+
+	/// Replace all the members with `members`.
+	/// Only the `ManagerOrigin` can reset the members.
+	pub fn reset_members(
+		runtime: &mut T,
+		caller: T::AccountID,
+		mut members: Vec<T::AccountID>,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		members.sort();
+		members.dedup();
+		if members.len() > T::MAX_MEMBERS {
+			return Err("Too many members");
+		}
+
+		let old_members = GetPallet::<Pallet<T>>::pallet(runtime).members.clone();
+		T::MembershipChanged::set_members_sorted(runtime, &members, &old_members);
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).members = members.clone();
+		Self::deposit_event(runtime, Event::MembersReset { members });
+		Ok(())
+	}
+
+	/// Replace the `caller`, which must be a member, with its new account `new`.
+	pub fn change_key(runtime: &mut T, caller: T::AccountID, new: T::AccountID) -> DispatchResult {
+		let members = GetPallet::<Pallet<T>>::pallet(runtime).swapped(&caller, &new)?;
+
+		Self::change_members(runtime, slice::from_ref(&new), slice::from_ref(&caller), members);
+		Self::deposit_event(runtime, Event::KeyChanged { old: caller, new });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{new_test_runtime, support, Runtime, RuntimeEvent};
+
+	type Membership = super::Pallet<Runtime>;
+
+	fn members(names: &[&str]) -> Vec<String> {
+		names.iter().map(|name| name.to_string()).collect()
+	}
+
+	#[test]
+	fn members_are_sorted_and_shared_with_collective() {
+		let mut runtime = new_test_runtime(&[]);
+		let initial = members(&["charlie", "alice", "bob"]);
+		let result = Membership::reset_members(&mut runtime, support::root_account(), initial);
+		assert_eq!(result, Ok(()));
+		assert_eq!(runtime.membership.members(), members(&["alice", "bob", "charlie"]));
+		assert_eq!(runtime.collective.members(), runtime.membership.members());
+	}
+
+	#[test]
+	fn add_remove_and_swap() {
+		let mut runtime = new_test_runtime(&[]);
+		let initial = members(&["charlie", "alice", "bob"]);
+		let result = Membership::reset_members(&mut runtime, support::root_account(), initial);
+		assert_eq!(result, Ok(()));
+		let root = support::root_account::<String>();
+
+		let result = Membership::add_member(&mut runtime, "alice".into(), "dave".into());
+		assert_eq!(result, Err("Bad origin"));
+		let result = Membership::add_member(&mut runtime, root.clone(), "bob".into());
+		assert_eq!(result, Err("Already a member"));
+		assert_eq!(Membership::add_member(&mut runtime, root.clone(), "dave".into()), Ok(()));
+		assert_eq!(runtime.collective.members(), members(&["alice", "bob", "charlie", "dave"]));
+
+		let result = Membership::remove_member(&mut runtime, root.clone(), "eve".into());
+		assert_eq!(result, Err("Not a member"));
+		assert_eq!(Membership::remove_member(&mut runtime, root.clone(), "bob".into()), Ok(()));
+		assert_eq!(runtime.collective.members(), members(&["alice", "charlie", "dave"]));
+
+		assert_eq!(
+			Membership::swap_member(&mut runtime, root.clone(), "alice".into(), "dave".into()),
+			Err("Already a member")
+		);
+		assert_eq!(
+			Membership::swap_member(&mut runtime, root.clone(), "alice".into(), "bob".into()),
+			Ok(())
+		);
+		assert_eq!(runtime.collective.members(), members(&["bob", "charlie", "dave"]));
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::membership(Event::MembersSwapped {
+				remove: "alice".into(),
+				add: "bob".into()
+			}))
+		);
+	}
+
+	#[test]
+	fn reset_members_and_change_key() {
+		let mut runtime = new_test_runtime(&[]);
+		let initial = members(&["charlie", "alice", "bob"]);
+		let result = Membership::reset_members(&mut runtime, support::root_account(), initial);
+		assert_eq!(result, Ok(()));
+		let root = support::root_account::<String>();
+
+		let result = Membership::change_key(&mut runtime, "dave".into(), "eve".into());
+		assert_eq!(result, Err("Not a member"));
+		assert_eq!(Membership::change_key(&mut runtime, "alice".into(), "eve".into()), Ok(()));
+		assert_eq!(runtime.collective.members(), members(&["bob", "charlie", "eve"]));
+
+		let too_many = (0..101).map(|i| i.to_string()).collect();
+		let result = Membership::reset_members(&mut runtime, root.clone(), too_many);
+		assert_eq!(result, Err("Too many members"));
+		let result = Membership::reset_members(&mut runtime, root, members(&["dave", "bob"]));
+		assert_eq!(result, Ok(()));
+		assert_eq!(runtime.membership.members(), members(&["bob", "dave"]));
+		assert_eq!(runtime.collective.members(), members(&["bob", "dave"]));
+	}
+}
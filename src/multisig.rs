@@ -0,0 +1,495 @@
+use crate::{
+	support::{self, CallHash, DeriveAccount, Dispatch, DispatchResult, GetPallet},
+	system,
+};
+use core::hash::Hash;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<AccountID: DeriveAccount + Hash>
+	+ Sized
+	+ Clone
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which multisig accounts can dispatch.
+	type RuntimeCall: Hash;
+	/// The maximum number of signatories a multisig account can have, including the caller.
+	const MAX_SIGNATORIES: usize;
+}
+
+/// A point in the history of the chain: a block number and the index of an extrinsic within that
+/// block. Used to refer to the extrinsic which opened a multisig operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timepoint<BlockNumber> {
+	/// The block number of the extrinsic.
+	pub height: BlockNumber,
+	/// The index of the extrinsic within the block.
+	pub index: u32,
+}
+
+/// An ongoing multisig operation, waiting for approvals.
+#[derive(Debug, Clone)]
+pub struct Multisig<BlockNumber, AccountID> {
+	/// The timepoint at which the operation was opened.
+	pub when: Timepoint<BlockNumber>,
+	/// The signatories who have approved the operation, starting with the one who opened it.
+	pub approvals: Vec<AccountID>,
+}
+
+/// The multisig operation type used by a runtime `T`.
+pub type MultisigOf<T> =
+	Multisig<<T as system::Config>::BlockNumber, <T as system::Config>::AccountID>;
+
+/// This is the Multisig Module.
+/// It lets a set of signatories control a shared account, which dispatches a call once enough of
+/// them have approved it.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from a multisig account and the hash of a call to the ongoing operation for that
+	/// call.
+	multisigs: BTreeMap<(T::AccountID, CallHash), MultisigOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Multisig Module.
+	pub fn new() -> Self {
+		Self { multisigs: BTreeMap::new() }
+	}
+
+	/// Derive the account controlled by a sorted set of `signatories` with the given `threshold`.
+	pub fn multi_account_id(signatories: &[T::AccountID], threshold: u16) -> T::AccountID {
+		T::AccountID::derive_account(&("multisig", signatories, threshold))
+	}
+
+	/// Get the ongoing operation of the `multisig` account for the call with hash `call_hash`.
+	pub fn multisig(&self, multisig: &T::AccountID, call_hash: CallHash) -> Option<&MultisigOf<T>> {
+		self.multisigs.get(&(multisig.clone(), call_hash))
+	}
+
+	/// Get the timepoint of the extrinsic currently being executed.
+	pub fn timepoint(runtime: &T) -> Timepoint<T::BlockNumber> {
+		let system = GetPallet::<system::Pallet<T>>::pallet(runtime);
+		Timepoint { height: system.block_number(), index: system.extrinsic_index().unwrap_or(0) }
+	}
+
+	/// Add the `caller` and `other_signatories` together into a sorted set of signatories, and
+	/// check it is valid for the given `threshold`.
+	fn signatories(
+		caller: T::AccountID,
+		mut other_signatories: Vec<T::AccountID>,
+		threshold: u16,
+	) -> Result<Vec<T::AccountID>, &'static str> {
+		if threshold < 2 {
+			return Err("Threshold must be at least 2");
+		}
+		other_signatories.push(caller);
+		other_signatories.sort();
+		if other_signatories.windows(2).any(|pair| pair[0] == pair[1]) {
+			return Err("Signatories must be unique");
+		}
+		if other_signatories.len() > T::MAX_SIGNATORIES {
+			return Err("Too many signatories");
+		}
+		if threshold as usize > other_signatories.len() {
+			return Err("Threshold is greater than the number of signatories");
+		}
+		Ok(other_signatories)
+	}
+
+	/// Approve the call with hash `call_hash` on behalf of the `caller`, and dispatch `call` from
+	/// the multisig account if it is given and has enough approvals.
+	fn operate(
+		runtime: &mut T,
+		caller: T::AccountID,
+		threshold: u16,
+		other_signatories: Vec<T::AccountID>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_hash: CallHash,
+		maybe_call: Option<T::RuntimeCall>,
+	) -> DispatchResult {
+		let signatories = Self::signatories(caller.clone(), other_signatories, threshold)?;
+		let id = Self::multi_account_id(&signatories, threshold);
+		let key = (id.clone(), call_hash);
+		let now = Self::timepoint(runtime);
+		let multisigs = &mut GetPallet::<Pallet<T>>::pallet_mut(runtime).multisigs;
+
+		let approvals = match multisigs.get_mut(&key) {
+			Some(multisig) => {
+				// The timepoint of the operation must be given, so that an approval cannot be
+				// replayed against a later operation for the same call.
+				let timepoint = maybe_timepoint.ok_or("Missing timepoint")?;
+				if multisig.when != timepoint {
+					return Err("Wrong timepoint");
+				}
+				if !multisig.approvals.contains(&caller) {
+					multisig.approvals.push(caller);
+				} else if maybe_call.is_none() || multisig.approvals.len() < threshold as usize {
+					return Err("Already approved");
+				}
+				multisig.approvals.len()
+			},
+			None => {
+				if maybe_timepoint.is_some() {
+					return Err("Unexpected timepoint");
+				}
+				multisigs.insert(key.clone(), Multisig { when: now, approvals: vec![caller] });
+				1
+			},
+		};
+
+		if let Some(call) = maybe_call {
+			if approvals >= threshold as usize {
+				multisigs.remove(&key);
+				return runtime.dispatch(id, call);
+			}
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Approve `call` from the multisig account of the `caller` and `other_signatories`, and
+	/// dispatch it if this brings the approvals up to the `threshold`.
+	/// The first approval opens the operation and must not give a timepoint; later approvals must
+	/// give the timepoint at which the operation was opened.
+	/// If the call fails, the approval is reverted along with it, and the error is returned.
+	pub fn as_multi(
+		runtime: &mut T,
+		caller: T::AccountID,
+		threshold: u16,
+		other_signatories: Vec<T::AccountID>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		let call_hash = support::hash_of(&call);
+		support::with_transaction(runtime, |runtime| {
+			Self::operate(
+				runtime,
+				caller,
+				threshold,
+				other_signatories,
+				maybe_timepoint,
+				call_hash,
+				Some(*call),
+			)
+		})
+	}
+
+	/// Approve the call with hash `call_hash` from the multisig account of the `caller` and
+	/// `other_signatories`, without dispatching it.
+	/// The call is dispatched by a later `as_multi` once the `threshold` is reached.
+	pub fn approve_as_multi(
+		runtime: &mut T,
+		caller: T::AccountID,
+		threshold: u16,
+		other_signatories: Vec<T::AccountID>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_hash: CallHash,
+	) -> DispatchResult {
+		Self::operate(
+			runtime,
+			caller,
+			threshold,
+			other_signatories,
+			maybe_timepoint,
+			call_hash,
+			None,
+		)
+	}
+
+	/// Cancel an ongoing operation for the call with hash `call_hash`.
+	/// This function will return an error if the operation does not exist at `timepoint`, or if
+	/// the `caller` is not the signatory who opened it.
+	pub fn cancel_as_multi(
+		runtime: &mut T,
+		caller: T::AccountID,
+		threshold: u16,
+		other_signatories: Vec<T::AccountID>,
+		timepoint: Timepoint<T::BlockNumber>,
+		call_hash: CallHash,
+	) -> DispatchResult {
+		let signatories = Self::signatories(caller.clone(), other_signatories, threshold)?;
+		let id = Self::multi_account_id(&signatories, threshold);
+		let multisig = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+
+		let operation = multisig.multisig(&id, call_hash).ok_or("Multisig operation not found")?;
+		if operation.when != timepoint {
+			return Err("Wrong timepoint");
+		}
+		if operation.approvals.first() != Some(&caller) {
+			return Err("Only the opener can cancel");
+		}
+		multisig.multisigs.remove(&(id, call_hash));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Timepoint;
+	use crate::{balances, new_test_runtime, run_to_block, support, Runtime, RuntimeCall};
+
+	type Multisig = super::Pallet<Runtime>;
+
+	fn transfer(to: &str, amount: u128) -> Box<RuntimeCall> {
+		let to = to.to_string().into();
+		Box::new(RuntimeCall::balances(balances::Call::transfer { to, amount }))
+	}
+
+	#[test]
+	fn as_multi_dispatches_at_threshold() {
+		let signatories = ["alice", "bob", "charlie"].map(String::from);
+		let multisig = Multisig::multi_account_id(&signatories, 2);
+		let mut runtime = new_test_runtime(&[(multisig.as_str(), 100)]);
+		run_to_block(&mut runtime, 1);
+		runtime.system.set_extrinsic_index(Some(3));
+		let timepoint = Timepoint { height: 1, index: 3 };
+		let others = vec!["charlie".to_string(), "bob".to_string()];
+
+		assert_eq!(
+			Multisig::as_multi(&mut runtime, "alice".into(), 2, others, None, transfer("dave", 60)),
+			Ok(())
+		);
+		let call_hash = support::hash_of(&transfer("dave", 60));
+		let operation = runtime.multisig.multisig(&multisig, call_hash).unwrap();
+		assert_eq!(operation.when, timepoint);
+		assert_eq!(operation.approvals, vec!["alice".to_string()]);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 0);
+
+		let others = vec!["alice".to_string(), "charlie".to_string()];
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				"bob".into(),
+				2,
+				others.clone(),
+				Some(timepoint),
+				transfer("dave", 60)
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 60);
+		assert_eq!(runtime.balances.balance(&multisig), 40);
+		assert!(runtime.multisig.multisig(&multisig, call_hash).is_none());
+
+		// The approval cannot be replayed to transfer again.
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				"bob".into(),
+				2,
+				others,
+				Some(timepoint),
+				transfer("dave", 60)
+			),
+			Err("Unexpected timepoint")
+		);
+	}
+
+	#[test]
+	fn failed_call_keeps_the_operation() {
+		let signatories = ["alice", "bob", "charlie"].map(String::from);
+		let multisig = Multisig::multi_account_id(&signatories, 2);
+		let mut runtime = new_test_runtime(&[(multisig.as_str(), 100)]);
+		run_to_block(&mut runtime, 1);
+		runtime.system.set_extrinsic_index(Some(3));
+		let timepoint = Timepoint { height: 1, index: 3 };
+		let call_hash = support::hash_of(&transfer("dave", 150));
+		let others = vec!["bob".to_string(), "charlie".to_string()];
+		assert_eq!(
+			Multisig::approve_as_multi(&mut runtime, "alice".into(), 2, others, None, call_hash),
+			Ok(())
+		);
+
+		// The transfer fails, so the approval of Bob is reverted with it.
+		let others = vec!["alice".to_string(), "charlie".to_string()];
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				"bob".into(),
+				2,
+				others.clone(),
+				Some(timepoint),
+				transfer("dave", 150)
+			),
+			Err("Not enough funds")
+		);
+		let operation = runtime.multisig.multisig(&multisig, call_hash).unwrap();
+		assert_eq!(operation.approvals, vec!["alice".to_string()]);
+
+		runtime.balances.set_balance(&multisig, 200);
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				"bob".into(),
+				2,
+				others,
+				Some(timepoint),
+				transfer("dave", 150)
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 150);
+		assert!(runtime.multisig.multisig(&multisig, call_hash).is_none());
+	}
+
+	#[test]
+	fn approve_as_multi_then_execute() {
+		let signatories = ["alice", "bob", "charlie"].map(String::from);
+		let multisig = Multisig::multi_account_id(&signatories, 2);
+		let mut runtime = new_test_runtime(&[(multisig.as_str(), 100)]);
+		run_to_block(&mut runtime, 1);
+		runtime.system.set_extrinsic_index(Some(3));
+		let timepoint = Timepoint { height: 1, index: 3 };
+		let call_hash = support::hash_of(&transfer("dave", 60));
+		let others = vec!["bob".to_string(), "charlie".to_string()];
+
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"alice".into(),
+				2,
+				others.clone(),
+				None,
+				call_hash
+			),
+			Ok(())
+		);
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"alice".into(),
+				2,
+				others,
+				Some(timepoint),
+				call_hash
+			),
+			Err("Already approved")
+		);
+
+		let others = vec!["alice".to_string(), "bob".to_string()];
+		let wrong_timepoint = Timepoint { height: 1, index: 2 };
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"charlie".into(),
+				2,
+				others.clone(),
+				Some(wrong_timepoint),
+				call_hash
+			),
+			Err("Wrong timepoint")
+		);
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"charlie".into(),
+				2,
+				others.clone(),
+				Some(timepoint),
+				call_hash
+			),
+			Ok(())
+		);
+		// The threshold is met, but the call is only dispatched once it is given.
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 0);
+		assert_eq!(
+			Multisig::as_multi(
+				&mut runtime,
+				"charlie".into(),
+				2,
+				others,
+				Some(timepoint),
+				transfer("dave", 60)
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 60);
+		assert_eq!(runtime.balances.balance(&multisig), 40);
+	}
+
+	#[test]
+	fn cancel_as_multi() {
+		let signatories = ["alice", "bob", "charlie"].map(String::from);
+		let multisig = Multisig::multi_account_id(&signatories, 2);
+		let mut runtime = new_test_runtime(&[(multisig.as_str(), 100)]);
+		run_to_block(&mut runtime, 1);
+		runtime.system.set_extrinsic_index(Some(3));
+		let timepoint = Timepoint { height: 1, index: 3 };
+		let call_hash = support::hash_of(&transfer("dave", 60));
+		let others = vec!["bob".to_string(), "charlie".to_string()];
+
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"alice".into(),
+				2,
+				others.clone(),
+				None,
+				call_hash
+			),
+			Ok(())
+		);
+		assert_eq!(
+			Multisig::cancel_as_multi(
+				&mut runtime,
+				"bob".into(),
+				2,
+				vec!["alice".to_string(), "charlie".to_string()],
+				timepoint,
+				call_hash
+			),
+			Err("Only the opener can cancel")
+		);
+		assert_eq!(
+			Multisig::cancel_as_multi(
+				&mut runtime,
+				"alice".into(),
+				2,
+				others,
+				timepoint,
+				call_hash
+			),
+			Ok(())
+		);
+		assert!(runtime.multisig.multisig(&multisig, call_hash).is_none());
+	}
+
+	#[test]
+	fn signatories_are_checked() {
+		let mut runtime = Runtime::new();
+		let call_hash = support::hash_of(&transfer("dave", 60));
+
+		assert_eq!(
+			Multisig::approve_as_multi(&mut runtime, "alice".into(), 1, vec![], None, call_hash),
+			Err("Threshold must be at least 2")
+		);
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"alice".into(),
+				2,
+				vec!["bob".to_string(), "alice".to_string()],
+				None,
+				call_hash
+			),
+			Err("Signatories must be unique")
+		);
+		assert_eq!(
+			Multisig::approve_as_multi(
+				&mut runtime,
+				"alice".into(),
+				3,
+				vec!["bob".to_string()],
+				None,
+				call_hash
+			),
+			Err("Threshold is greater than the number of signatories")
+		);
+	}
+}
@@ -0,0 +1,302 @@
+use crate::{
+	balances,
+	support::{self, Convert, DispatchResult, GetPallet},
+	system,
+};
+use num::{
+	traits::{CheckedAdd, CheckedMul},
+	Zero,
+};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID, Self::BlockNumber>>>
+	+ balances::Config<Balance: CheckedMul>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// Converts a number of blocks into a balance, so that the rent of a name can be computed.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, Self::Balance>;
+	/// The rent paid for every block a name is registered for.
+	const RENT_PER_BLOCK: Self::Balance;
+	/// The maximum number of blocks a name can be registered or renewed for at once.
+	const MAX_REGISTRATION_LENGTH: Self::BlockNumber;
+	/// The maximum length of a name.
+	const MAX_NAME_LENGTH: usize;
+}
+
+/// A registered name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration<AccountID, BlockNumber> {
+	/// The account which owns the name, and can transfer it or change its target.
+	pub owner: AccountID,
+	/// The account the name resolves to.
+	pub target: AccountID,
+	/// The block at which the name expires, unless it is renewed.
+	pub expiry: BlockNumber,
+}
+
+/// The registration type used by a runtime `T`.
+pub type RegistrationOf<T> =
+	Registration<<T as system::Config>::AccountID, <T as system::Config>::BlockNumber>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, BlockNumber> {
+	/// A name was registered until the `expiry` block.
+	Registered { name: String, owner: AccountID, expiry: BlockNumber },
+	/// A name was renewed until the `expiry` block.
+	Renewed { name: String, expiry: BlockNumber },
+	/// A name was transferred to a new owner.
+	Transferred { name: String, from: AccountID, to: AccountID },
+	/// A name now resolves to `target`.
+	TargetSet { name: String, target: AccountID },
+	/// A name expired, and can be registered again.
+	Expired { name: String },
+}
+
+/// This is the Names Module.
+/// It lets accounts register human readable names, like `alice.chain`, which resolve to an
+/// account. Names are rented for a number of blocks, and expire unless they are renewed.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from a name to its registration.
+	names: BTreeMap<String, RegistrationOf<T>>,
+	/// A map from a block to the names which expire at that block.
+	expiries: BTreeMap<T::BlockNumber, Vec<String>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Names Module.
+	pub fn new() -> Self {
+		Self { names: BTreeMap::new(), expiries: BTreeMap::new() }
+	}
+
+	/// Get the account `name` resolves to.
+	pub fn resolve(&self, name: &str) -> Option<&T::AccountID> {
+		self.names.get(name).map(|registration| &registration.target)
+	}
+
+	/// Get the registration of `name`.
+	pub fn registration(&self, name: &str) -> Option<&RegistrationOf<T>> {
+		self.names.get(name)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::BlockNumber>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Burn the rent of `duration` blocks from `who`.
+	fn pay_rent(runtime: &mut T, who: &T::AccountID, duration: T::BlockNumber) -> DispatchResult {
+		if duration.is_zero() {
+			return Err("Duration is zero");
+		}
+		if duration > T::MAX_REGISTRATION_LENGTH {
+			return Err("Duration is too long");
+		}
+		let rent = T::RENT_PER_BLOCK
+			.checked_mul(&T::BlockNumberToBalance::convert(duration))
+			.ok_or("Balance overflow error")?;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).burn(who, rent)
+	}
+
+	/// Move `name` from the expiries of block `from` to the expiries of block `to`.
+	fn move_expiry(&mut self, name: &str, from: T::BlockNumber, to: T::BlockNumber) {
+		if let Some(names) = self.expiries.get_mut(&from) {
+			names.retain(|expiring| expiring != name);
+			if names.is_empty() {
+				self.expiries.remove(&from);
+			}
+		}
+		self.expiries.entry(to).or_default().push(name.to_string());
+	}
+
+	/// Get the registration of `name`, checking that it is owned by `who`.
+	fn owned_registration(
+		&mut self,
+		who: &T::AccountID,
+		name: &str,
+	) -> Result<&mut RegistrationOf<T>, &'static str> {
+		let registration = self.names.get_mut(name).ok_or("Name not registered")?;
+		if &registration.owner != who {
+			return Err("Not the owner");
+		}
+		Ok(registration)
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Remove the names which expire at block `n`.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		let names = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let expired = names.expiries.remove(&n).unwrap_or_default();
+		for name in expired {
+			GetPallet::<Pallet<T>>::pallet_mut(runtime).names.remove(&name);
+			Self::deposit_event(runtime, Event::Expired { name });
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Register `name` for the `caller` for `duration` blocks, burning the rent for them.
+	/// The name resolves to the `caller` until its target is changed.
+	pub fn register(
+		runtime: &mut T,
+		caller: T::AccountID,
+		name: String,
+		duration: T::BlockNumber,
+	) -> DispatchResult {
+		if name.is_empty() || name.len() > T::MAX_NAME_LENGTH {
+			return Err("Invalid name length");
+		}
+		if GetPallet::<Pallet<T>>::pallet(runtime).names.contains_key(&name) {
+			return Err("Name already registered");
+		}
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let expiry = now.checked_add(&duration).ok_or("Block number overflow")?;
+		Self::pay_rent(runtime, &caller, duration)?;
+
+		let names = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let registration = Registration { owner: caller.clone(), target: caller.clone(), expiry };
+		names.names.insert(name.clone(), registration);
+		names.expiries.entry(expiry).or_default().push(name.clone());
+		Self::deposit_event(runtime, Event::Registered { name, owner: caller, expiry });
+		Ok(())
+	}
+
+	/// Extend the registration of `name` by `duration` blocks, burning the rent for them from the
+	/// `caller`. Anyone can renew a name.
+	pub fn renew(
+		runtime: &mut T,
+		caller: T::AccountID,
+		name: String,
+		duration: T::BlockNumber,
+	) -> DispatchResult {
+		let names = GetPallet::<Pallet<T>>::pallet(runtime);
+		let old_expiry = names.names.get(&name).ok_or("Name not registered")?.expiry;
+		let expiry = old_expiry.checked_add(&duration).ok_or("Block number overflow")?;
+		Self::pay_rent(runtime, &caller, duration)?;
+
+		let names = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		names.move_expiry(&name, old_expiry, expiry);
+		if let Some(registration) = names.names.get_mut(&name) {
+			registration.expiry = expiry;
+		}
+		Self::deposit_event(runtime, Event::Renewed { name, expiry });
+		Ok(())
+	}
+
+	/// Transfer the ownership of `name` to `to`. Its target is not changed.
+	pub fn transfer_name(
+		runtime: &mut T,
+		caller: T::AccountID,
+		name: String,
+		to: T::AccountID,
+	) -> DispatchResult {
+		let names = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		names.owned_registration(&caller, &name)?.owner = to.clone();
+		Self::deposit_event(runtime, Event::Transferred { name, from: caller, to });
+		Ok(())
+	}
+
+	/// Make `name` resolve to `target`.
+	pub fn set_target(
+		runtime: &mut T,
+		caller: T::AccountID,
+		name: String,
+		target: T::AccountID,
+	) -> DispatchResult {
+		let names = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		names.owned_registration(&caller, &name)?.target = target.clone();
+		Self::deposit_event(runtime, Event::TargetSet { name, target });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{new_test_runtime, run_to_block, treasury, Runtime};
+
+	type Names = super::Pallet<Runtime>;
+
+	#[test]
+	fn register_name() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Names::register(&mut runtime, "alice".into(), "alice.chain".into(), 10);
+		assert_eq!(result, Ok(()));
+		let bob = "bob".to_string();
+		assert_eq!(runtime.names.resolve("alice.chain"), Some(&"alice".to_string()));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 90);
+		assert_eq!(runtime.balances.balance(&treasury::Pallet::<Runtime>::account_id()), 10);
+
+		assert_eq!(
+			Names::register(&mut runtime, bob.clone(), "alice.chain".into(), 10),
+			Err("Name already registered")
+		);
+		assert_eq!(
+			Names::register(&mut runtime, bob.clone(), "".into(), 10),
+			Err("Invalid name length")
+		);
+		assert_eq!(
+			Names::register(&mut runtime, bob.clone(), "bob.chain".into(), 0),
+			Err("Duration is zero")
+		);
+		assert_eq!(
+			Names::register(&mut runtime, bob.clone(), "bob.chain".into(), 1_000),
+			Err("Duration is too long")
+		);
+		assert_eq!(
+			Names::register(&mut runtime, bob.clone(), "bob.chain".into(), 200),
+			Err("Not enough funds")
+		);
+		assert_eq!(Names::register(&mut runtime, bob.clone(), "bob.chain".into(), 20), Ok(()));
+		assert_eq!(runtime.names.resolve("bob.chain"), Some(&bob));
+	}
+
+	#[test]
+	fn transfer_and_set_target() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Names::register(&mut runtime, "alice".into(), "alice.chain".into(), 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let name = "alice.chain".to_string();
+
+		let result = Names::set_target(&mut runtime, bob.clone(), name.clone(), bob.clone());
+		assert_eq!(result, Err("Not the owner"));
+		let result = Names::set_target(&mut runtime, alice.clone(), name.clone(), "dave".into());
+		assert_eq!(result, Ok(()));
+		assert_eq!(runtime.names.resolve(&name), Some(&"dave".to_string()));
+
+		let result = Names::transfer_name(&mut runtime, alice.clone(), name.clone(), bob.clone());
+		assert_eq!(result, Ok(()));
+		let result = Names::set_target(&mut runtime, alice, name.clone(), "charlie".into());
+		assert_eq!(result, Err("Not the owner"));
+		assert_eq!(runtime.names.registration(&name).unwrap().owner, bob);
+		assert_eq!(runtime.names.resolve(&name), Some(&"dave".to_string()));
+	}
+
+	#[test]
+	fn expiry_and_renewal() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("bob", 100)]);
+		let result = Names::register(&mut runtime, "alice".into(), "alice.chain".into(), 10);
+		assert_eq!(result, Ok(()));
+		let name = "alice.chain".to_string();
+
+		// Anyone can renew a name, and it expires at its new expiry.
+		assert_eq!(Names::renew(&mut runtime, "bob".into(), name.clone(), 5), Ok(()));
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 95);
+		run_to_block(&mut runtime, 14);
+		assert!(runtime.names.resolve(&name).is_some());
+		run_to_block(&mut runtime, 15);
+		assert!(runtime.names.resolve(&name).is_none());
+		let result = Names::renew(&mut runtime, "bob".into(), name.clone(), 5);
+		assert_eq!(result, Err("Name not registered"));
+
+		assert_eq!(Names::register(&mut runtime, "bob".into(), name.clone(), 5), Ok(()));
+		assert_eq!(runtime.names.registration(&name).unwrap().expiry, 20);
+	}
+}
@@ -0,0 +1,218 @@
+use crate::{
+	support::{self, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use core::fmt::Debug;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID, Self::OracleKey, Self::OracleValue>>>
+	+ Sized
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The key identifying a data feed, such as the name of a price pair.
+	type OracleKey: Debug + Clone + Ord;
+	/// The value of a data feed.
+	type OracleValue: Debug + Clone + Ord;
+	/// The origin which can set the feeders.
+	type FeederOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The maximum number of feeders.
+	const MAX_FEEDERS: usize;
+}
+
+/// A value of a data feed, with the block it was aggregated at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampedValue<Value, BlockNumber> {
+	/// The median of the values submitted by the feeders.
+	pub value: Value,
+	/// The block at which the value was aggregated.
+	pub block_number: BlockNumber,
+}
+
+/// The timestamped value type used by a runtime `T`.
+pub type TimestampedValueOf<T> =
+	TimestampedValue<<T as Config>::OracleValue, <T as system::Config>::BlockNumber>;
+
+/// The values submitted in the current block, by key and feeder.
+type RawValuesOf<T> = BTreeMap<
+	<T as Config>::OracleKey,
+	BTreeMap<<T as system::Config>::AccountID, <T as Config>::OracleValue>,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, OracleKey, OracleValue> {
+	/// A feeder submitted values.
+	NewFeedData { sender: AccountID, values: Vec<(OracleKey, OracleValue)> },
+	/// The values submitted for a key in this block were aggregated into `value`.
+	ValueAggregated { key: OracleKey, value: OracleValue },
+}
+
+/// This is the Oracle Module.
+/// It lets a set of authorized feeders submit off-chain values, such as prices, and aggregates
+/// them into a median at the end of each block for other pallets to read.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The accounts which can submit values, sorted.
+	feeders: Vec<T::AccountID>,
+	/// The values submitted in the current block.
+	raw_values: RawValuesOf<T>,
+	/// A map from a key to its latest aggregated value.
+	values: BTreeMap<T::OracleKey, TimestampedValueOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Oracle Module.
+	pub fn new() -> Self {
+		Self { feeders: Vec::new(), raw_values: BTreeMap::new(), values: BTreeMap::new() }
+	}
+
+	/// Get the accounts which can submit values.
+	pub fn feeders(&self) -> &[T::AccountID] {
+		&self.feeders
+	}
+
+	/// Get the latest aggregated value of `key`, and the block it was aggregated at.
+	pub fn get(&self, key: &T::OracleKey) -> Option<&TimestampedValueOf<T>> {
+		self.values.get(key)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::OracleKey, T::OracleValue>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Aggregate the values submitted in block `n`. With an even number of values, the lower of
+	/// the two middle values is used as the median.
+	fn on_finalize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		let oracle = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let raw_values = core::mem::take(&mut oracle.raw_values);
+		for (key, submitted) in raw_values {
+			let mut values = submitted.into_values().collect::<Vec<_>>();
+			values.sort();
+			let value = values.swap_remove((values.len() - 1) / 2);
+
+			let aggregated = TimestampedValue { value: value.clone(), block_number: n };
+			let oracle = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+			oracle.values.insert(key.clone(), aggregated);
+			Self::deposit_event(runtime, Event::ValueAggregated { key, value });
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Set the accounts which can submit values.
+	/// Only the `FeederOrigin` can set the feeders.
+	pub fn set_feeders(
+		runtime: &mut T,
+		caller: T::AccountID,
+		mut feeders: Vec<T::AccountID>,
+	) -> DispatchResult {
+		T::FeederOrigin::ensure_origin(runtime, &caller)?;
+		feeders.sort();
+		feeders.dedup();
+		if feeders.len() > T::MAX_FEEDERS {
+			return Err("Too many feeders");
+		}
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).feeders = feeders;
+		Ok(())
+	}
+
+	/// Submit `values` for some keys as a feeder. Each feeder can submit a single value per key in
+	/// a block.
+	pub fn feed_values(
+		runtime: &mut T,
+		caller: T::AccountID,
+		values: Vec<(T::OracleKey, T::OracleValue)>,
+	) -> DispatchResult {
+		let oracle = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if oracle.feeders.binary_search(&caller).is_err() {
+			return Err("Not a feeder");
+		}
+		let already_fed = values.iter().any(|(key, _)| {
+			oracle
+				.raw_values
+				.get(key)
+				.is_some_and(|submitted| submitted.contains_key(&caller))
+		});
+		if already_fed {
+			return Err("Already fed in this block");
+		}
+
+		for (key, value) in &values {
+			let submitted = oracle.raw_values.entry(key.clone()).or_default();
+			// A key given twice in `values` keeps the last value.
+			submitted.insert(caller.clone(), value.clone());
+		}
+		Self::deposit_event(runtime, Event::NewFeedData { sender: caller, values });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{new_test_runtime, run_to_block, support, Runtime, RuntimeEvent};
+
+	type Oracle = super::Pallet<Runtime>;
+
+	#[test]
+	fn set_feeders() {
+		let mut runtime = new_test_runtime(&[]);
+		let feeders = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		assert_eq!(Oracle::set_feeders(&mut runtime, support::root_account(), feeders), Ok(()));
+		let result = Oracle::set_feeders(&mut runtime, "alice".into(), Vec::new());
+		assert_eq!(result, Err("Bad origin"));
+		let feeders = vec!["bob".to_string(), "alice".to_string(), "bob".to_string()];
+		assert_eq!(Oracle::set_feeders(&mut runtime, support::root_account(), feeders), Ok(()));
+		assert_eq!(runtime.oracle.feeders(), &["alice".to_string(), "bob".to_string()]);
+	}
+
+	#[test]
+	fn median_of_feeds() {
+		let mut runtime = new_test_runtime(&[]);
+		let feeders = ["alice", "bob", "charlie"].map(String::from).to_vec();
+		assert_eq!(Oracle::set_feeders(&mut runtime, support::root_account(), feeders), Ok(()));
+		run_to_block(&mut runtime, 1);
+
+		assert_eq!(
+			Oracle::feed_values(&mut runtime, "dave".into(), vec![("DOT", 5)]),
+			Err("Not a feeder")
+		);
+		assert_eq!(
+			Oracle::feed_values(&mut runtime, "alice".into(), vec![("DOT", 5), ("BTC", 100)]),
+			Ok(())
+		);
+		assert_eq!(
+			Oracle::feed_values(&mut runtime, "alice".into(), vec![("DOT", 6)]),
+			Err("Already fed in this block")
+		);
+		assert_eq!(
+			Oracle::feed_values(&mut runtime, "bob".into(), vec![("DOT", 9), ("BTC", 120)]),
+			Ok(())
+		);
+		assert_eq!(Oracle::feed_values(&mut runtime, "charlie".into(), vec![("DOT", 7)]), Ok(()));
+		assert!(runtime.oracle.get(&"DOT").is_none());
+
+		// The values are aggregated when the next block is finalized.
+		run_to_block(&mut runtime, 2);
+		let dot = runtime.oracle.get(&"DOT").unwrap();
+		assert_eq!((dot.value, dot.block_number), (7, 2));
+		let btc = runtime.oracle.get(&"BTC").unwrap();
+		assert_eq!((btc.value, btc.block_number), (100, 2));
+		assert!(runtime
+			.system
+			.events()
+			.contains(&RuntimeEvent::oracle(Event::ValueAggregated { key: "DOT", value: 7 })));
+
+		// Feeders can submit again in the next block, and keys without new values keep theirs.
+		assert_eq!(Oracle::feed_values(&mut runtime, "alice".into(), vec![("DOT", 8)]), Ok(()));
+		run_to_block(&mut runtime, 3);
+		assert_eq!(runtime.oracle.get(&"DOT").unwrap().value, 8);
+		assert_eq!(runtime.oracle.get(&"BTC").unwrap().block_number, 2);
+	}
+}
@@ -0,0 +1,280 @@
+use crate::{
+	balances,
+	support::{self, CallHash, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use core::{fmt::Debug, hash::Hash};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event>>
+	+ balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, whose values are stored as preimages.
+	type RuntimeCall: Clone + Debug + Hash;
+	/// The origin which can request and unrequest preimages.
+	type ManagerOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The deposit reserved for noting a preimage which was not requested.
+	const PREIMAGE_DEPOSIT: Self::Balance;
+}
+
+/// Whether a preimage is requested, and who paid a deposit for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestStatus<AccountID, Balance> {
+	/// The preimage was noted by `depositor` without being requested, and can be unnoted by it.
+	Unrequested { depositor: AccountID, deposit: Balance },
+	/// The preimage was requested `count` times. It may have been noted with a deposit before it
+	/// was requested, and it is kept until every request is removed.
+	Requested { maybe_deposit: Option<(AccountID, Balance)>, count: u32 },
+}
+
+/// The request status type used by a runtime `T`.
+pub type RequestStatusOf<T> =
+	RequestStatus<<T as system::Config>::AccountID, <T as balances::Config>::Balance>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+	/// A preimage was noted.
+	Noted { hash: CallHash },
+	/// A preimage was requested.
+	Requested { hash: CallHash },
+	/// A preimage was removed.
+	Cleared { hash: CallHash },
+}
+
+/// This is the Preimage Module.
+/// It stores calls by their hash, so that other pallets can refer to a large call with its hash
+/// and look it up once it is needed. A preimage noted by an account holds a deposit, unless it was
+/// requested by the `ManagerOrigin`.
+///
+/// Our runtime has no encoding for calls, so a preimage is the `RuntimeCall` itself, and its hash
+/// is given by `support::hash_of`.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from a hash to whether its preimage is requested.
+	status_for: BTreeMap<CallHash, RequestStatusOf<T>>,
+	/// A map from a hash to its preimage.
+	preimages: BTreeMap<CallHash, T::RuntimeCall>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Preimage Module.
+	pub fn new() -> Self {
+		Self { status_for: BTreeMap::new(), preimages: BTreeMap::new() }
+	}
+
+	/// Get the request status of `hash`.
+	pub fn status_for(&self, hash: CallHash) -> Option<&RequestStatusOf<T>> {
+		self.status_for.get(&hash)
+	}
+
+	/// Get the call with hash `hash`, if its preimage was noted.
+	pub fn realize(&self, hash: CallHash) -> Option<T::RuntimeCall> {
+		self.preimages.get(&hash).cloned()
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Remove a preimage and its request status.
+	fn clear(runtime: &mut T, hash: CallHash) {
+		let preimage = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		preimage.status_for.remove(&hash);
+		preimage.preimages.remove(&hash);
+		Self::deposit_event(runtime, Event::Cleared { hash });
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Store `call` as a preimage, so that it can be looked up by its hash. The `PREIMAGE_DEPOSIT`
+	/// is reserved from the `caller`, unless the preimage was requested.
+	pub fn note_preimage(
+		runtime: &mut T,
+		caller: T::AccountID,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		let hash = support::hash_of(&call);
+		let preimage = GetPallet::<Pallet<T>>::pallet(runtime);
+		if preimage.preimages.contains_key(&hash) {
+			return Err("Already noted");
+		}
+
+		let status = match preimage.status_for.get(&hash) {
+			Some(status @ RequestStatus::Requested { .. }) => status.clone(),
+			_ => {
+				let deposit = T::PREIMAGE_DEPOSIT;
+				GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, deposit)?;
+				RequestStatus::Unrequested { depositor: caller, deposit }
+			},
+		};
+		let preimage = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		preimage.status_for.insert(hash, status);
+		preimage.preimages.insert(hash, *call);
+		Self::deposit_event(runtime, Event::Noted { hash });
+		Ok(())
+	}
+
+	/// Return the deposit for the preimage of `hash`, noted by the `caller`. The preimage is
+	/// removed, unless it is requested.
+	pub fn unnote_preimage(
+		runtime: &mut T,
+		caller: T::AccountID,
+		hash: CallHash,
+	) -> DispatchResult {
+		let preimage = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let status = preimage.status_for.get_mut(&hash).ok_or("Not noted")?;
+		let (deposit, requested) = match status {
+			RequestStatus::Unrequested { depositor, deposit } if *depositor == caller =>
+				(*deposit, false),
+			RequestStatus::Requested { maybe_deposit, .. } => {
+				let (_, deposit) = maybe_deposit
+					.take_if(|(depositor, _)| *depositor == caller)
+					.ok_or("Not the depositor")?;
+				(deposit, true)
+			},
+			_ => return Err("Not the depositor"),
+		};
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).unreserve(&caller, deposit);
+		if !requested {
+			Self::clear(runtime, hash);
+		}
+		Ok(())
+	}
+
+	/// Request the preimage of `hash`, so that it is kept until the request is removed, and can be
+	/// noted without a deposit. A preimage can be requested multiple times.
+	/// Only the `ManagerOrigin` can request preimages.
+	pub fn request_preimage(
+		runtime: &mut T,
+		caller: T::AccountID,
+		hash: CallHash,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		let preimage = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let status = match preimage.status_for.get(&hash).cloned() {
+			None => RequestStatus::Requested { maybe_deposit: None, count: 1 },
+			Some(RequestStatus::Unrequested { depositor, deposit }) =>
+				RequestStatus::Requested { maybe_deposit: Some((depositor, deposit)), count: 1 },
+			Some(RequestStatus::Requested { maybe_deposit, count }) => {
+				let count = count.checked_add(1).ok_or("Request count overflow")?;
+				RequestStatus::Requested { maybe_deposit, count }
+			},
+		};
+		preimage.status_for.insert(hash, status);
+		Self::deposit_event(runtime, Event::Requested { hash });
+		Ok(())
+	}
+
+	/// Remove a request for the preimage of `hash`. Once every request is removed, the preimage is
+	/// removed too, unless an account paid a deposit for it.
+	/// Only the `ManagerOrigin` can unrequest preimages.
+	pub fn unrequest_preimage(
+		runtime: &mut T,
+		caller: T::AccountID,
+		hash: CallHash,
+	) -> DispatchResult {
+		T::ManagerOrigin::ensure_origin(runtime, &caller)?;
+		let preimage = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let status = match preimage.status_for.get(&hash) {
+			Some(RequestStatus::Requested { maybe_deposit, count }) if *count > 1 =>
+				RequestStatus::Requested { maybe_deposit: maybe_deposit.clone(), count: count - 1 },
+			Some(RequestStatus::Requested {
+				maybe_deposit: Some((depositor, deposit)), ..
+			}) => RequestStatus::Unrequested { depositor: depositor.clone(), deposit: *deposit },
+			Some(RequestStatus::Requested { maybe_deposit: None, .. }) => {
+				Self::clear(runtime, hash);
+				return Ok(());
+			},
+			_ => return Err("Not requested"),
+		};
+		preimage.status_for.insert(hash, status);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::RequestStatus;
+	use crate::{balances, new_test_runtime, support, treasury, Runtime, RuntimeCall};
+
+	type Preimage = super::Pallet<Runtime>;
+
+	fn approve_spend() -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::treasury(treasury::Call::approve_proposal { proposal_index: 0 }))
+	}
+
+	#[test]
+	fn note_and_unnote() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let hash = support::hash_of(&approve_spend());
+
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), approve_spend()), Ok(()));
+		let result = Preimage::note_preimage(&mut runtime, alice.clone(), approve_spend());
+		assert_eq!(result, Err("Already noted"));
+		assert_eq!(runtime.preimage.realize(hash), Some(*approve_spend()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+
+		let result = Preimage::unnote_preimage(&mut runtime, "bob".into(), hash);
+		assert_eq!(result, Err("Not the depositor"));
+		assert_eq!(Preimage::unnote_preimage(&mut runtime, alice.clone(), hash), Ok(()));
+		assert_eq!(Preimage::unnote_preimage(&mut runtime, alice.clone(), hash), Err("Not noted"));
+		assert!(runtime.preimage.realize(hash).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+	}
+
+	#[test]
+	fn requested_preimage_is_free_and_kept() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let bob = "bob".to_string();
+		let root = support::root_account::<String>();
+		let hash = support::hash_of(&approve_spend());
+
+		let result = Preimage::request_preimage(&mut runtime, bob.clone(), hash);
+		assert_eq!(result, Err("Bad origin"));
+		assert_eq!(Preimage::request_preimage(&mut runtime, root.clone(), hash), Ok(()));
+		assert_eq!(Preimage::request_preimage(&mut runtime, root.clone(), hash), Ok(()));
+
+		// Bob has no funds, but the preimage was requested so it needs no deposit.
+		assert_eq!(Preimage::note_preimage(&mut runtime, bob, approve_spend()), Ok(()));
+		let status = runtime.preimage.status_for(hash);
+		assert_eq!(status, Some(&RequestStatus::Requested { maybe_deposit: None, count: 2 }));
+
+		assert_eq!(Preimage::unrequest_preimage(&mut runtime, root.clone(), hash), Ok(()));
+		assert!(runtime.preimage.realize(hash).is_some());
+		assert_eq!(Preimage::unrequest_preimage(&mut runtime, root.clone(), hash), Ok(()));
+		assert!(runtime.preimage.realize(hash).is_none());
+		assert_eq!(Preimage::unrequest_preimage(&mut runtime, root, hash), Err("Not requested"));
+	}
+
+	#[test]
+	fn noted_then_requested() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let root = support::root_account::<String>();
+		let call = Box::new(RuntimeCall::balances(balances::Call::transfer {
+			to: "bob".to_string().into(),
+			amount: 10,
+		}));
+		let hash = support::hash_of(&call);
+
+		assert_eq!(Preimage::note_preimage(&mut runtime, alice.clone(), call), Ok(()));
+		assert_eq!(Preimage::request_preimage(&mut runtime, root.clone(), hash), Ok(()));
+
+		// The depositor can get its deposit back, but the preimage is kept while it is requested.
+		assert_eq!(Preimage::unnote_preimage(&mut runtime, alice.clone(), hash), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert!(runtime.preimage.realize(hash).is_some());
+		assert_eq!(Preimage::unrequest_preimage(&mut runtime, root, hash), Ok(()));
+		assert!(runtime.preimage.realize(hash).is_none());
+	}
+}
@@ -1,4 +1,4 @@
-use crate::support::DispatchResult;
+use crate::support::{self, DispatchResult};
 use core::fmt::Debug;
 use std::collections::BTreeMap;
 
@@ -11,7 +11,7 @@ pub trait Config: crate::system::Config {
 
 /// This is the Proof of Existence Module.
 /// It is a simple module that allows accounts to claim existence of some data.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Pallet<T: Config> {
 	/// A simple storage map from content to the owner of that content.
 	/// Accounts can make multiple different claims, but each claim can only have one owner.
@@ -30,13 +30,15 @@ impl<T: Config> Pallet<T> {
 	}
 }
 
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
 #[macros::call]
 impl<T: Config> Pallet<T> {
 	/// Create a new claim on behalf of the `caller`.
 	/// This function will return an error if someone already has claimed that content.
 	pub fn create_claim(&mut self, caller: T::AccountID, claim: T::Content) -> DispatchResult {
 		if self.claims.contains_key(&claim) {
-			return Err(&"this content is already claimed");
+			return Err("this content is already claimed");
 		}
 		let _res = self.claims.insert(claim, caller);
 		Ok(())
@@ -69,6 +71,7 @@ mod test {
 		type AccountID = &'static str;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type RuntimeEvent = ();
 	}
 
 	#[test]
@@ -83,8 +86,8 @@ mod test {
 		let _res = poe.create_claim(alice, claim);
 		assert_eq!(poe.get_claim(&claim), Some(&alice));
 
-		assert_eq!(poe.create_claim(&bob, &claim), Err("this content is already claimed"));
-		assert_eq!(poe.revoke_claim(&alice, &claim), Ok(()));
-		assert_eq!(poe.create_claim(&bob, &claim), Ok(()));
+		assert_eq!(poe.create_claim(bob, claim), Err("this content is already claimed"));
+		assert_eq!(poe.revoke_claim(alice, claim), Ok(()));
+		assert_eq!(poe.create_claim(bob, claim), Ok(()));
 	}
 }
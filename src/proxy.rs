@@ -0,0 +1,376 @@
+use crate::{
+	support::{self, CallHash, Dispatch, DispatchResult, GetPallet, InstanceFilter},
+	system,
+};
+use core::{fmt::Debug, hash::Hash};
+use num::{traits::CheckedAdd, Zero};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config
+	+ Sized
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which proxies can dispatch.
+	type RuntimeCall: Hash;
+	/// The kinds of proxies, each of which filters the calls a proxy is allowed to make.
+	type ProxyType: InstanceFilter<Self::RuntimeCall> + Copy + Eq + Hash + Debug;
+	/// The maximum number of proxies an account can have.
+	const MAX_PROXIES: usize;
+	/// The maximum number of announcements a proxy can have pending at the same time.
+	const MAX_PENDING: usize;
+}
+
+/// A proxy which can make calls on behalf of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyDefinition<AccountID, ProxyType, BlockNumber> {
+	/// The account which can make calls.
+	pub delegate: AccountID,
+	/// The kind of proxy, which filters the calls the delegate can make.
+	pub proxy_type: ProxyType,
+	/// The number of blocks a call must be announced for before the delegate can make it.
+	pub delay: BlockNumber,
+}
+
+/// A call which a proxy has announced it will make on behalf of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Announcement<AccountID, BlockNumber> {
+	/// The account the call will be made on behalf of.
+	pub real: AccountID,
+	/// The hash of the announced call.
+	pub call_hash: CallHash,
+	/// The block at which the call was announced.
+	pub height: BlockNumber,
+}
+
+/// The proxy definition type used by a runtime `T`.
+pub type ProxyDefinitionOf<T> = ProxyDefinition<
+	<T as system::Config>::AccountID,
+	<T as Config>::ProxyType,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The announcement type used by a runtime `T`.
+pub type AnnouncementOf<T> =
+	Announcement<<T as system::Config>::AccountID, <T as system::Config>::BlockNumber>;
+
+/// This is the Proxy Module.
+/// It lets accounts delegate the ability to make some calls on their behalf to other accounts.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from an account to the proxies which can make calls on its behalf.
+	proxies: BTreeMap<T::AccountID, Vec<ProxyDefinitionOf<T>>>,
+	/// A map from a proxy to the calls it has announced.
+	announcements: BTreeMap<T::AccountID, Vec<AnnouncementOf<T>>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Proxy Module.
+	pub fn new() -> Self {
+		Self { proxies: BTreeMap::new(), announcements: BTreeMap::new() }
+	}
+
+	/// Get the proxies of an account.
+	pub fn proxies(&self, real: &T::AccountID) -> &[ProxyDefinitionOf<T>] {
+		self.proxies.get(real).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// Get the calls announced by a proxy.
+	pub fn announcements(&self, delegate: &T::AccountID) -> &[AnnouncementOf<T>] {
+		self.announcements.get(delegate).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// Find the proxy definition which lets `delegate` make calls on behalf of `real`, with the
+	/// given proxy type if one is forced.
+	fn find_proxy(
+		&self,
+		real: &T::AccountID,
+		delegate: &T::AccountID,
+		force_proxy_type: Option<T::ProxyType>,
+	) -> Result<ProxyDefinitionOf<T>, &'static str> {
+		self.proxies(real)
+			.iter()
+			.find(|proxy| {
+				&proxy.delegate == delegate &&
+					force_proxy_type.is_none_or(|proxy_type| proxy.proxy_type == proxy_type)
+			})
+			.cloned()
+			.ok_or("Not a proxy")
+	}
+
+	/// Remove the announcement by `delegate` of the call with hash `call_hash` on behalf of `real`,
+	/// checking that it was made at least `delay` blocks ago.
+	fn take_announcement(
+		runtime: &mut T,
+		delegate: &T::AccountID,
+		real: &T::AccountID,
+		call_hash: CallHash,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let announcements = GetPallet::<Pallet<T>>::pallet_mut(runtime)
+			.announcements
+			.get_mut(delegate)
+			.ok_or("Unannounced")?;
+		let index = announcements
+			.iter()
+			.position(|announcement| {
+				&announcement.real == real && announcement.call_hash == call_hash
+			})
+			.ok_or("Unannounced")?;
+		let ready_at = announcements[index].height.checked_add(&delay).ok_or("Overflow error")?;
+		if now < ready_at {
+			return Err("Announcement delay has not passed");
+		}
+		announcements.remove(index);
+		if announcements.is_empty() {
+			GetPallet::<Pallet<T>>::pallet_mut(runtime).announcements.remove(delegate);
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Let `delegate` make the calls allowed by `proxy_type` on behalf of the `caller`.
+	/// If `delay` is not zero, the delegate must announce each call and wait `delay` blocks before
+	/// making it, giving the caller time to reject it.
+	pub fn add_proxy(
+		&mut self,
+		caller: T::AccountID,
+		delegate: T::AccountID,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		let proxy = ProxyDefinition { delegate, proxy_type, delay };
+		let proxies = self.proxies.entry(caller).or_default();
+		if proxies.contains(&proxy) {
+			return Err("Proxy already exists");
+		}
+		if proxies.len() >= T::MAX_PROXIES {
+			return Err("Too many proxies");
+		}
+		proxies.push(proxy);
+		Ok(())
+	}
+
+	/// Remove a proxy of the `caller`.
+	/// This function will return an error if the proxy does not exist.
+	pub fn remove_proxy(
+		&mut self,
+		caller: T::AccountID,
+		delegate: T::AccountID,
+		proxy_type: T::ProxyType,
+		delay: T::BlockNumber,
+	) -> DispatchResult {
+		let proxy = ProxyDefinition { delegate, proxy_type, delay };
+		let proxies = self.proxies.get_mut(&caller).ok_or("Proxy not found")?;
+		let index = proxies.iter().position(|p| p == &proxy).ok_or("Proxy not found")?;
+		proxies.remove(index);
+		if proxies.is_empty() {
+			self.proxies.remove(&caller);
+		}
+		Ok(())
+	}
+
+	/// Announce that the `caller` will make the call with hash `call_hash` on behalf of `real`.
+	/// This function will return an error if the caller is not a proxy of `real`.
+	pub fn announce(
+		runtime: &mut T,
+		caller: T::AccountID,
+		real: T::AccountID,
+		call_hash: CallHash,
+	) -> DispatchResult {
+		let height = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let proxy = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		proxy.find_proxy(&real, &caller, None)?;
+
+		let announcements = proxy.announcements.entry(caller).or_default();
+		if announcements.len() >= T::MAX_PENDING {
+			return Err("Too many pending announcements");
+		}
+		announcements.push(Announcement { real, call_hash, height });
+		Ok(())
+	}
+
+	/// Reject the announcement by `delegate` of the call with hash `call_hash` on behalf of the
+	/// `caller`, so that it can never be made.
+	pub fn reject_announcement(
+		&mut self,
+		caller: T::AccountID,
+		delegate: T::AccountID,
+		call_hash: CallHash,
+	) -> DispatchResult {
+		let announcements =
+			self.announcements.get_mut(&delegate).ok_or("Announcement not found")?;
+		let count = announcements.len();
+		announcements.retain(|announcement| {
+			announcement.real != caller || announcement.call_hash != call_hash
+		});
+		if announcements.len() == count {
+			return Err("Announcement not found");
+		}
+		if announcements.is_empty() {
+			self.announcements.remove(&delegate);
+		}
+		Ok(())
+	}
+
+	/// Dispatch `call` on behalf of `real`, using a proxy of `real` held by the `caller`.
+	/// If `force_proxy_type` is given, only a proxy of that type is used. The call must be allowed
+	/// by the type of the proxy, and must have been announced if the proxy has a delay.
+	pub fn proxy(
+		runtime: &mut T,
+		caller: T::AccountID,
+		real: T::AccountID,
+		force_proxy_type: Option<T::ProxyType>,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		let proxy =
+			GetPallet::<Pallet<T>>::pallet(runtime).find_proxy(&real, &caller, force_proxy_type)?;
+		if !proxy.proxy_type.filter(&call) {
+			return Err("Call is not allowed by the proxy type");
+		}
+		if !proxy.delay.is_zero() {
+			let call_hash = support::hash_of(&call);
+			Self::take_announcement(runtime, &caller, &real, call_hash, proxy.delay)?;
+		}
+		runtime.dispatch(real, *call)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		balances, new_test_runtime, proof_of_existence, support, ProxyType, Runtime, RuntimeCall,
+	};
+
+	type Proxy = super::Pallet<Runtime>;
+
+	fn transfer(to: &str, amount: u128) -> Box<RuntimeCall> {
+		let to = to.to_string().into();
+		Box::new(RuntimeCall::balances(balances::Call::transfer { to, amount }))
+	}
+
+	fn create_claim(claim: &'static str) -> Box<RuntimeCall> {
+		Box::new(RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim }))
+	}
+
+	#[test]
+	fn proxy_checks_filter() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Err("Not a proxy")
+		);
+
+		let proxy = &mut runtime.proxy;
+		assert_eq!(proxy.add_proxy(alice.clone(), bob.clone(), ProxyType::Transfer, 0), Ok(()));
+		assert_eq!(
+			proxy.add_proxy(alice.clone(), bob.clone(), ProxyType::Transfer, 0),
+			Err("Proxy already exists")
+		);
+
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, create_claim("0xclaim")),
+			Err("Call is not allowed by the proxy type")
+		);
+
+		let proxy = &mut runtime.proxy;
+		assert_eq!(proxy.add_proxy(alice.clone(), bob.clone(), ProxyType::Claims, 0), Ok(()));
+		assert_eq!(
+			Proxy::proxy(
+				&mut runtime,
+				bob.clone(),
+				alice.clone(),
+				Some(ProxyType::Transfer),
+				create_claim("0xclaim")
+			),
+			Err("Call is not allowed by the proxy type")
+		);
+		assert_eq!(
+			Proxy::proxy(
+				&mut runtime,
+				bob.clone(),
+				alice.clone(),
+				Some(ProxyType::Claims),
+				create_claim("0xclaim")
+			),
+			Ok(())
+		);
+		assert_eq!(runtime.proof_of_existence.get_claim(&"0xclaim"), Some(&alice));
+
+		let proxy = &mut runtime.proxy;
+		assert_eq!(proxy.remove_proxy(alice.clone(), bob.clone(), ProxyType::Claims, 0), Ok(()));
+		assert_eq!(proxy.proxies(&alice).len(), 1);
+	}
+
+	#[test]
+	fn proxy_with_delay() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let call_hash = support::hash_of(&transfer("bob", 10));
+
+		assert_eq!(runtime.proxy.add_proxy(alice.clone(), bob.clone(), ProxyType::Any, 2), Ok(()));
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Err("Unannounced")
+		);
+
+		assert_eq!(Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash), Ok(()));
+		runtime.system.inc_block_number();
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Err("Announcement delay has not passed")
+		);
+
+		runtime.system.inc_block_number();
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert!(runtime.proxy.announcements(&bob).is_empty());
+	}
+
+	#[test]
+	fn reject_announcement() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let call_hash = support::hash_of(&transfer("bob", 10));
+
+		assert_eq!(
+			Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash),
+			Err("Not a proxy")
+		);
+		assert_eq!(runtime.proxy.add_proxy(alice.clone(), bob.clone(), ProxyType::Any, 2), Ok(()));
+		assert_eq!(Proxy::announce(&mut runtime, bob.clone(), alice.clone(), call_hash), Ok(()));
+
+		assert_eq!(
+			runtime.proxy.reject_announcement(bob.clone(), bob.clone(), call_hash),
+			Err("Announcement not found")
+		);
+		let proxy = &mut runtime.proxy;
+		assert_eq!(proxy.reject_announcement(alice.clone(), bob.clone(), call_hash), Ok(()));
+
+		runtime.system.inc_block_number();
+		runtime.system.inc_block_number();
+		assert_eq!(
+			Proxy::proxy(&mut runtime, bob.clone(), alice.clone(), None, transfer("bob", 10)),
+			Err("Unannounced")
+		);
+	}
+}
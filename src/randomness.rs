@@ -0,0 +1,112 @@
+use crate::{
+	support::{self, BlockHash, DispatchResult, GetPallet},
+	system,
+};
+use core::marker::PhantomData;
+use num::{traits::CheckedSub, Zero};
+use std::collections::VecDeque;
+
+pub trait Config:
+	system::Config<BlockNumber: From<u32>>
+	+ Sized
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The number of recent parent hashes mixed into the randomness.
+	const RANDOM_MATERIAL_LEN: u32;
+}
+
+/// This is the Randomness Module.
+/// It keeps the hashes of recent blocks, and mixes them with a subject to produce randomness, like
+/// a collective coin flip of the recent block authors.
+///
+/// Every block author chooses the hash of its block, so the randomness can be biased by the
+/// authors of the last `RANDOM_MATERIAL_LEN` blocks. It must not be used for high-stakes outcomes.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The hashes of the most recent blocks, oldest first.
+	random_material: VecDeque<BlockHash>,
+	_marker: PhantomData<T>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Randomness Module.
+	pub fn new() -> Self {
+		Self { random_material: VecDeque::new(), _marker: PhantomData }
+	}
+
+	/// Get the hashes of the most recent blocks, oldest first.
+	#[allow(unused)]
+	pub fn random_material(&self) -> &VecDeque<BlockHash> {
+		&self.random_material
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Add the hash of the parent block to the random material, replacing the oldest one once
+	/// there are `RANDOM_MATERIAL_LEN` of them.
+	fn on_initialize(runtime: &mut T, _n: T::BlockNumber) -> DispatchResult {
+		let parent_hash = GetPallet::<system::Pallet<T>>::pallet(runtime).parent_hash();
+		let randomness = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if randomness.random_material.len() >= T::RANDOM_MATERIAL_LEN as usize {
+			randomness.random_material.pop_front();
+		}
+		randomness.random_material.push_back(parent_hash);
+		Ok(())
+	}
+}
+
+// The randomness is only read by other pallets, so this pallet has no calls.
+#[macros::call]
+impl<T: Config> Pallet<T> {}
+
+impl<T: Config> support::Randomness<T, BlockHash, T::BlockNumber> for Pallet<T> {
+	/// Mix the random material with `subject`. Any of the last `RANDOM_MATERIAL_LEN` block authors
+	/// could have influenced the result, so it is only safe for commitments made before them.
+	fn random(runtime: &T, subject: &[u8]) -> (BlockHash, T::BlockNumber) {
+		let randomness = GetPallet::<Pallet<T>>::pallet(runtime);
+		let output = support::hash_of(&(subject, &randomness.random_material));
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let known_since = now
+			.checked_sub(&T::RANDOM_MATERIAL_LEN.into())
+			.unwrap_or(T::BlockNumber::zero());
+		(output, known_since)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{run_to_block, support::Randomness as _, Runtime};
+
+	type Randomness = super::Pallet<Runtime>;
+
+	#[test]
+	fn random_material_is_a_ring() {
+		let mut runtime = Runtime::new();
+		run_to_block(&mut runtime, 1);
+		assert_eq!(runtime.randomness.random_material().iter().collect::<Vec<_>>(), [&0]);
+
+		run_to_block(&mut runtime, 99);
+		let parent_hash = runtime.system.parent_hash();
+		run_to_block(&mut runtime, 100);
+		let material = runtime.randomness.random_material();
+		assert_eq!(material.len(), 81);
+		assert_eq!(material.back(), Some(&parent_hash));
+		assert!(!material.contains(&0));
+	}
+
+	#[test]
+	fn random_depends_on_subject_and_blocks() {
+		let mut runtime = Runtime::new();
+		run_to_block(&mut runtime, 5);
+		let (first, known_since) = Randomness::random(&runtime, b"lottery");
+		assert_eq!(known_since, 0);
+		assert_eq!(Randomness::random(&runtime, b"lottery").0, first);
+		assert_ne!(Randomness::random(&runtime, b"nft").0, first);
+
+		run_to_block(&mut runtime, 100);
+		let (second, known_since) = Randomness::random(&runtime, b"lottery");
+		assert_eq!(known_since, 19);
+		assert_ne!(second, first);
+	}
+}
@@ -0,0 +1,407 @@
+use crate::{
+	balances::{self, BalanceStatus},
+	support::{self, Dispatch, DispatchResult, GetPallet},
+	system,
+};
+use num::traits::CheckedAdd;
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::AccountID>>>
+	+ balances::Config
+	+ Sized
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which can be dispatched for a recovered account.
+	type RuntimeCall;
+	/// The deposit reserved for making an account recoverable.
+	const CONFIG_DEPOSIT: Self::Balance;
+	/// The deposit reserved for initiating the recovery of an account.
+	const RECOVERY_DEPOSIT: Self::Balance;
+	/// The maximum number of friends of a recoverable account.
+	const MAX_FRIENDS: usize;
+}
+
+/// How an account can be recovered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryConfig<AccountID, Balance, BlockNumber> {
+	/// The number of blocks after a recovery is initiated before it can be claimed.
+	pub delay_period: BlockNumber,
+	/// The amount reserved for this configuration.
+	pub deposit: Balance,
+	/// The friends which can vouch for a recovery, sorted.
+	pub friends: Vec<AccountID>,
+	/// The number of friends which must vouch for a recovery.
+	pub threshold: u16,
+}
+
+/// A recovery of an account which is in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveRecovery<AccountID, Balance, BlockNumber> {
+	/// The block at which the recovery was initiated.
+	pub created: BlockNumber,
+	/// The amount reserved by the rescuer.
+	pub deposit: Balance,
+	/// The friends which vouched for the recovery, sorted.
+	pub friends: Vec<AccountID>,
+}
+
+/// The recovery config type used by a runtime `T`.
+pub type RecoveryConfigOf<T> = RecoveryConfig<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The active recovery type used by a runtime `T`.
+pub type ActiveRecoveryOf<T> = ActiveRecovery<
+	<T as system::Config>::AccountID,
+	<T as balances::Config>::Balance,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID> {
+	/// An account was made recoverable.
+	RecoveryCreated { account: AccountID },
+	/// The `rescuer` initiated the recovery of `lost`.
+	RecoveryInitiated { lost: AccountID, rescuer: AccountID },
+	/// A friend of `lost` vouched for its recovery by `rescuer`.
+	RecoveryVouched { lost: AccountID, rescuer: AccountID, sender: AccountID },
+	/// The `rescuer` claimed `lost`, and can now make calls on its behalf.
+	AccountRecovered { lost: AccountID, rescuer: AccountID },
+	/// The `lost` account closed its recovery by `rescuer`, taking the deposit of the rescuer.
+	RecoveryClosed { lost: AccountID, rescuer: AccountID },
+	/// An account is no longer recoverable.
+	RecoveryRemoved { account: AccountID },
+}
+
+/// This is the Recovery Module.
+/// It lets accounts name friends who can vouch for a rescuer, so that an account whose key was
+/// lost can be taken over by a new one after a delay.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from a recoverable account to how it can be recovered.
+	recoverable: BTreeMap<T::AccountID, RecoveryConfigOf<T>>,
+	/// A map from a lost account to its recoveries in progress, by rescuer.
+	active_recoveries: BTreeMap<T::AccountID, BTreeMap<T::AccountID, ActiveRecoveryOf<T>>>,
+	/// A map from a rescuer to the account it recovered.
+	proxy: BTreeMap<T::AccountID, T::AccountID>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Recovery Module.
+	pub fn new() -> Self {
+		Self {
+			recoverable: BTreeMap::new(),
+			active_recoveries: BTreeMap::new(),
+			proxy: BTreeMap::new(),
+		}
+	}
+
+	/// Get how `account` can be recovered.
+	pub fn recovery_config(&self, account: &T::AccountID) -> Option<&RecoveryConfigOf<T>> {
+		self.recoverable.get(account)
+	}
+
+	/// Get the recovery of `lost` by `rescuer`, if it is in progress.
+	pub fn active_recovery(
+		&self,
+		lost: &T::AccountID,
+		rescuer: &T::AccountID,
+	) -> Option<&ActiveRecoveryOf<T>> {
+		self.active_recoveries.get(lost)?.get(rescuer)
+	}
+
+	/// Get the account recovered by `rescuer`.
+	pub fn proxy(&self, rescuer: &T::AccountID) -> Option<&T::AccountID> {
+		self.proxy.get(rescuer)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Make the `caller` recoverable, once `threshold` of its `friends` vouch for a rescuer and
+	/// `delay_period` blocks have passed. The `CONFIG_DEPOSIT` is reserved.
+	pub fn create_recovery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		mut friends: Vec<T::AccountID>,
+		threshold: u16,
+		delay_period: T::BlockNumber,
+	) -> DispatchResult {
+		friends.sort();
+		friends.dedup();
+		if friends.is_empty() {
+			return Err("Not enough friends");
+		}
+		if friends.len() > T::MAX_FRIENDS {
+			return Err("Too many friends");
+		}
+		if threshold == 0 || threshold as usize > friends.len() {
+			return Err("Bad threshold");
+		}
+		if GetPallet::<Pallet<T>>::pallet(runtime).recoverable.contains_key(&caller) {
+			return Err("Already recoverable");
+		}
+
+		let deposit = T::CONFIG_DEPOSIT;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, deposit)?;
+		let config = RecoveryConfig { delay_period, deposit, friends, threshold };
+		GetPallet::<Pallet<T>>::pallet_mut(runtime)
+			.recoverable
+			.insert(caller.clone(), config);
+		Self::deposit_event(runtime, Event::RecoveryCreated { account: caller });
+		Ok(())
+	}
+
+	/// Start recovering `account` for the `caller`, reserving the `RECOVERY_DEPOSIT`.
+	pub fn initiate_recovery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		account: T::AccountID,
+	) -> DispatchResult {
+		let recovery = GetPallet::<Pallet<T>>::pallet(runtime);
+		if !recovery.recoverable.contains_key(&account) {
+			return Err("Not recoverable");
+		}
+		if recovery.active_recovery(&account, &caller).is_some() {
+			return Err("Recovery already started");
+		}
+
+		let deposit = T::RECOVERY_DEPOSIT;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, deposit)?;
+		let created = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let active = ActiveRecovery { created, deposit, friends: Vec::new() };
+		let recovery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let recoveries = recovery.active_recoveries.entry(account.clone()).or_default();
+		recoveries.insert(caller.clone(), active);
+		Self::deposit_event(runtime, Event::RecoveryInitiated { lost: account, rescuer: caller });
+		Ok(())
+	}
+
+	/// Vouch for the recovery of `lost` by `rescuer`, as a friend of `lost`.
+	pub fn vouch_recovery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		lost: T::AccountID,
+		rescuer: T::AccountID,
+	) -> DispatchResult {
+		let recovery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let config = recovery.recoverable.get(&lost).ok_or("Not recoverable")?;
+		if config.friends.binary_search(&caller).is_err() {
+			return Err("Not a friend");
+		}
+		let active = recovery
+			.active_recoveries
+			.get_mut(&lost)
+			.and_then(|recoveries| recoveries.get_mut(&rescuer))
+			.ok_or("Recovery not started")?;
+		match active.friends.binary_search(&caller) {
+			Ok(_) => return Err("Already vouched"),
+			Err(position) => active.friends.insert(position, caller.clone()),
+		}
+
+		Self::deposit_event(runtime, Event::RecoveryVouched { lost, rescuer, sender: caller });
+		Ok(())
+	}
+
+	/// Claim `account` for the `caller`, once enough friends vouched for its recovery and the
+	/// delay period has passed. Afterwards, the `caller` can make calls on behalf of `account`.
+	pub fn claim_recovery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		account: T::AccountID,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let recovery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let config = recovery.recoverable.get(&account).ok_or("Not recoverable")?;
+		let active = recovery.active_recovery(&account, &caller).ok_or("Recovery not started")?;
+		let claimable_at = active
+			.created
+			.checked_add(&config.delay_period)
+			.ok_or("Block number overflow")?;
+		if now < claimable_at {
+			return Err("Delay period has not passed");
+		}
+		if active.friends.len() < config.threshold as usize {
+			return Err("Threshold not reached");
+		}
+		if recovery.proxy.contains_key(&caller) {
+			return Err("Already recovering an account");
+		}
+
+		recovery.proxy.insert(caller.clone(), account.clone());
+		Self::deposit_event(runtime, Event::AccountRecovered { lost: account, rescuer: caller });
+		Ok(())
+	}
+
+	/// Dispatch `call` on behalf of `account`, which was recovered by the `caller`.
+	pub fn as_recovered(
+		runtime: &mut T,
+		caller: T::AccountID,
+		account: T::AccountID,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		if GetPallet::<Pallet<T>>::pallet(runtime).proxy(&caller) != Some(&account) {
+			return Err("Not allowed");
+		}
+		runtime.dispatch(account, *call)
+	}
+
+	/// Close the recovery of the `caller` by `rescuer`. The deposit of the rescuer is given to the
+	/// `caller`. A recovered account can close its recoveries through `as_recovered`.
+	pub fn close_recovery(
+		runtime: &mut T,
+		caller: T::AccountID,
+		rescuer: T::AccountID,
+	) -> DispatchResult {
+		let recovery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let recoveries =
+			recovery.active_recoveries.get_mut(&caller).ok_or("Recovery not started")?;
+		let active = recoveries.remove(&rescuer).ok_or("Recovery not started")?;
+		if recoveries.is_empty() {
+			recovery.active_recoveries.remove(&caller);
+		}
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.repatriate_reserved(&rescuer, &caller, active.deposit, BalanceStatus::Free);
+		Self::deposit_event(runtime, Event::RecoveryClosed { lost: caller, rescuer });
+		Ok(())
+	}
+
+	/// Make the `caller` no longer recoverable, returning its deposit. All recoveries of the
+	/// `caller` must be closed first.
+	pub fn remove_recovery(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let recovery = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if recovery.active_recoveries.contains_key(&caller) {
+			return Err("Recovery still active");
+		}
+		let config = recovery.recoverable.remove(&caller).ok_or("Not recoverable")?;
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).unreserve(&caller, config.deposit);
+		Self::deposit_event(runtime, Event::RecoveryRemoved { account: caller });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{balances, new_test_runtime, run_to_block, Runtime, RuntimeCall};
+
+	type Recovery = super::Pallet<Runtime>;
+
+	fn transfer(to: &str, amount: u128) -> Box<RuntimeCall> {
+		let to = to.to_string().into();
+		Box::new(RuntimeCall::balances(balances::Call::transfer { to, amount }))
+	}
+
+	#[test]
+	fn create_recovery() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("eve", 100)]);
+		let friends = ["bob", "charlie", "dave"].map(String::from).to_vec();
+		let result = Recovery::create_recovery(&mut runtime, "alice".into(), friends, 2, 10);
+		assert_eq!(result, Ok(()));
+		let eve = "eve".to_string();
+		assert_eq!(runtime.balances.reserved_balance(&"alice".to_string()), 10);
+
+		let friends = vec!["bob".to_string(), "bob".to_string()];
+		let result = Recovery::create_recovery(&mut runtime, eve.clone(), friends.clone(), 2, 10);
+		assert_eq!(result, Err("Bad threshold"));
+		let result = Recovery::create_recovery(&mut runtime, eve.clone(), Vec::new(), 1, 10);
+		assert_eq!(result, Err("Not enough friends"));
+		assert_eq!(Recovery::create_recovery(&mut runtime, eve.clone(), friends, 1, 10), Ok(()));
+		assert_eq!(runtime.recovery.recovery_config(&eve).unwrap().friends, &["bob".to_string()]);
+
+		assert_eq!(Recovery::remove_recovery(&mut runtime, eve.clone()), Ok(()));
+		assert_eq!(Recovery::remove_recovery(&mut runtime, eve.clone()), Err("Not recoverable"));
+		assert_eq!(runtime.balances.reserved_balance(&eve), 0);
+	}
+
+	#[test]
+	fn recover_account() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("eve", 100)]);
+		let friends = ["bob", "charlie", "dave"].map(String::from).to_vec();
+		let result = Recovery::create_recovery(&mut runtime, "alice".into(), friends, 2, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let eve = "eve".to_string();
+
+		assert_eq!(
+			Recovery::vouch_recovery(&mut runtime, "bob".into(), alice.clone(), eve.clone()),
+			Err("Recovery not started")
+		);
+		assert_eq!(Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&eve), 20);
+
+		assert_eq!(
+			Recovery::vouch_recovery(&mut runtime, "eve".into(), alice.clone(), eve.clone()),
+			Err("Not a friend")
+		);
+		assert_eq!(
+			Recovery::vouch_recovery(&mut runtime, "bob".into(), alice.clone(), eve.clone()),
+			Ok(())
+		);
+		assert_eq!(
+			Recovery::vouch_recovery(&mut runtime, "bob".into(), alice.clone(), eve.clone()),
+			Err("Already vouched")
+		);
+		let result = Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone());
+		assert_eq!(result, Err("Delay period has not passed"));
+
+		run_to_block(&mut runtime, 10);
+		let result = Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone());
+		assert_eq!(result, Err("Threshold not reached"));
+		assert_eq!(
+			Recovery::vouch_recovery(&mut runtime, "dave".into(), alice.clone(), eve.clone()),
+			Ok(())
+		);
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 1)),
+			Err("Not allowed")
+		);
+		assert_eq!(Recovery::claim_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		assert_eq!(runtime.recovery.proxy(&eve), Some(&alice));
+
+		// The rescuer can now move the funds of the lost account.
+		assert_eq!(
+			Recovery::as_recovered(&mut runtime, eve.clone(), alice.clone(), transfer("eve", 90)),
+			Ok(())
+		);
+		assert_eq!(runtime.balances.balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&eve), 170);
+	}
+
+	#[test]
+	fn close_recovery() {
+		let mut runtime = new_test_runtime(&[("alice", 100), ("eve", 100)]);
+		let friends = ["bob", "charlie", "dave"].map(String::from).to_vec();
+		let result = Recovery::create_recovery(&mut runtime, "alice".into(), friends, 2, 10);
+		assert_eq!(result, Ok(()));
+		let alice = "alice".to_string();
+		let eve = "eve".to_string();
+
+		assert_eq!(Recovery::initiate_recovery(&mut runtime, eve.clone(), alice.clone()), Ok(()));
+		let result = Recovery::remove_recovery(&mut runtime, alice.clone());
+		assert_eq!(result, Err("Recovery still active"));
+
+		// The lost account still has its key, and takes the deposit of the malicious rescuer.
+		assert_eq!(Recovery::close_recovery(&mut runtime, alice.clone(), eve.clone()), Ok(()));
+		assert!(runtime.recovery.active_recovery(&alice, &eve).is_none());
+		assert_eq!(runtime.balances.reserved_balance(&eve), 0);
+		assert_eq!(runtime.balances.balance(&eve), 80);
+		assert_eq!(runtime.balances.balance(&alice), 110);
+		assert_eq!(Recovery::remove_recovery(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 120);
+	}
+}
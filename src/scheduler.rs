@@ -0,0 +1,441 @@
+use crate::{
+	support::{self, Dispatch, DispatchResult, GetPallet},
+	system,
+};
+use core::fmt::Debug;
+use num::{traits::CheckedAdd, Zero};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<RuntimeEvent: From<Event<Self::BlockNumber>>>
+	+ Sized
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The overarching call type, which can be scheduled.
+	type RuntimeCall: Clone + Debug;
+	/// The maximum number of calls which can be scheduled for, and dispatched in, a single block.
+	const MAX_SCHEDULED_PER_BLOCK: usize;
+}
+
+/// The name of a named schedule, which can be used to cancel it.
+pub type TaskName = &'static str;
+
+/// The priority of a scheduled call. Calls with a lower value are dispatched first.
+pub type Priority = u8;
+
+/// A scheduled call can be identified by the block it is scheduled for, and its index in the
+/// agenda of that block.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// A period and a count: the call will be dispatched `count` times, every `period` blocks.
+pub type Period<BlockNumber> = (BlockNumber, u32);
+
+/// A call which is scheduled to be dispatched at some block.
+#[derive(Debug, Clone)]
+pub struct Scheduled<AccountID, Call, BlockNumber> {
+	/// The name of the schedule, if it is named.
+	pub maybe_id: Option<TaskName>,
+	/// The priority of the call within its block.
+	pub priority: Priority,
+	/// The call to dispatch.
+	pub call: Call,
+	/// The period of the call, if it is periodic.
+	pub maybe_periodic: Option<Period<BlockNumber>>,
+	/// The account which scheduled the call, and which it is dispatched from.
+	pub origin: AccountID,
+}
+
+/// The scheduled call type used by a runtime `T`.
+pub type ScheduledOf<T> = Scheduled<
+	<T as system::Config>::AccountID,
+	<T as Config>::RuntimeCall,
+	<T as system::Config>::BlockNumber,
+>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<BlockNumber> {
+	/// A scheduled call was dispatched, with the given `result`.
+	Dispatched { task: TaskAddress<BlockNumber>, id: Option<TaskName>, result: DispatchResult },
+	/// A periodic call could not be scheduled again, since the agenda of its next block is full.
+	PeriodicFailed { task: TaskAddress<BlockNumber>, id: Option<TaskName> },
+}
+
+/// This is the Scheduler Module.
+/// It lets accounts schedule calls to be dispatched at a future block, optionally repeating every
+/// few blocks.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from a block number to the calls scheduled for that block. Cancelled calls are left
+	/// as `None`, so that the indices of the other calls do not change.
+	agenda: BTreeMap<T::BlockNumber, Vec<Option<ScheduledOf<T>>>>,
+	/// A map from the name of a named schedule to where its next call is in the agenda.
+	lookup: BTreeMap<TaskName, TaskAddress<T::BlockNumber>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Scheduler Module.
+	pub fn new() -> Self {
+		Self { agenda: BTreeMap::new(), lookup: BTreeMap::new() }
+	}
+
+	/// Get the calls scheduled for block `n`.
+	pub fn agenda(&self, n: T::BlockNumber) -> &[Option<ScheduledOf<T>>] {
+		self.agenda.get(&n).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// Get where the next call of a named schedule is in the agenda.
+	pub fn lookup(&self, id: TaskName) -> Option<TaskAddress<T::BlockNumber>> {
+		self.lookup.get(id).copied()
+	}
+
+	/// Add `task` to the agenda of block `when`, returning its address.
+	fn place_task(
+		&mut self,
+		when: T::BlockNumber,
+		task: ScheduledOf<T>,
+	) -> Result<TaskAddress<T::BlockNumber>, &'static str> {
+		let agenda = self.agenda.entry(when).or_default();
+		if agenda.iter().flatten().count() >= T::MAX_SCHEDULED_PER_BLOCK {
+			return Err("Agenda is full");
+		}
+		let maybe_id = task.maybe_id;
+		agenda.push(Some(task));
+		let address = (when, agenda.len() as u32 - 1);
+		if let Some(id) = maybe_id {
+			self.lookup.insert(id, address);
+		}
+		Ok(address)
+	}
+
+	/// Schedule `call` to be dispatched from the `caller` at block `when`.
+	fn do_schedule(
+		runtime: &mut T,
+		caller: T::AccountID,
+		maybe_id: Option<TaskName>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: T::RuntimeCall,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		if when <= now {
+			return Err("Target block number is in the past");
+		}
+		// A period which only dispatches the call once is the same as no period.
+		let maybe_periodic = match maybe_periodic {
+			Some((period, _)) if period.is_zero() => return Err("Period must not be zero"),
+			Some((_, count)) if count <= 1 => None,
+			maybe_periodic => maybe_periodic,
+		};
+
+		let scheduler = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if maybe_id.is_some_and(|id| scheduler.lookup.contains_key(id)) {
+			return Err("Name is already in use");
+		}
+		let task = Scheduled { maybe_id, priority, call, maybe_periodic, origin: caller };
+		scheduler.place_task(when, task)?;
+		Ok(())
+	}
+
+	/// Remove the call at `address` from the agenda, if it was scheduled by the `caller`.
+	fn do_cancel(
+		&mut self,
+		caller: &T::AccountID,
+		(when, index): TaskAddress<T::BlockNumber>,
+	) -> DispatchResult {
+		let task = self
+			.agenda
+			.get_mut(&when)
+			.and_then(|agenda| agenda.get_mut(index as usize))
+			.ok_or("Scheduled call not found")?;
+		match task {
+			Some(scheduled) if &scheduled.origin != caller =>
+				Err("Caller did not schedule the call"),
+			Some(scheduled) => {
+				if let Some(id) = scheduled.maybe_id {
+					self.lookup.remove(id);
+				}
+				*task = None;
+				Ok(())
+			},
+			None => Err("Scheduled call not found"),
+		}
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Dispatch the calls scheduled for block `n`, in order of priority, and schedule the next call
+	/// of any periodic schedules.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		let scheduler = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let agenda = scheduler.agenda.remove(&n).unwrap_or_default();
+		let mut tasks = agenda
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, task)| task.map(|task| ((n, index as u32), task)))
+			.collect::<Vec<_>>();
+		// The sort is stable, so calls with the same priority keep the order they were scheduled
+		// in.
+		tasks.sort_by_key(|(_, task)| task.priority);
+
+		for (address, task) in tasks {
+			let scheduler = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+			let id = task.maybe_id;
+			if let Some(id) = id {
+				scheduler.lookup.remove(id);
+			}
+
+			if let Some((period, count)) = task.maybe_periodic {
+				let maybe_periodic = if count > 2 { Some((period, count - 1)) } else { None };
+				let next = Scheduled { maybe_periodic, ..task.clone() };
+				let placed = n
+					.checked_add(&period)
+					.ok_or("Overflow error")
+					.and_then(|when| scheduler.place_task(when, next));
+				if placed.is_err() {
+					let event = Event::PeriodicFailed { task: address, id };
+					GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+				}
+			}
+
+			let result = runtime.dispatch(task.origin, task.call);
+			let event = Event::Dispatched { task: address, id, result };
+			GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Schedule `call` to be dispatched from the `caller` at block `when`, and then every `period`
+	/// blocks for a total of `count` times if `maybe_periodic` is `Some((period, count))`.
+	/// Calls scheduled for the same block are dispatched in order of `priority`.
+	pub fn schedule(
+		runtime: &mut T,
+		caller: T::AccountID,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		Self::do_schedule(runtime, caller, None, when, maybe_periodic, priority, *call)
+	}
+
+	/// Cancel the call at `index` in the agenda of block `when`.
+	/// This function will return an error if the call does not exist, or if it was not scheduled by
+	/// the `caller`.
+	pub fn cancel(
+		&mut self,
+		caller: T::AccountID,
+		when: T::BlockNumber,
+		index: u32,
+	) -> DispatchResult {
+		self.do_cancel(&caller, (when, index))
+	}
+
+	/// Schedule `call` like `schedule`, under the name `id` which can be used to cancel it.
+	/// This function will return an error if the name is already in use.
+	pub fn schedule_named(
+		runtime: &mut T,
+		caller: T::AccountID,
+		id: TaskName,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		Self::do_schedule(runtime, caller, Some(id), when, maybe_periodic, priority, *call)
+	}
+
+	/// Cancel the named schedule `id`, including any future calls if it is periodic.
+	/// This function will return an error if the schedule does not exist, or if it was not
+	/// scheduled by the `caller`.
+	pub fn cancel_named(&mut self, caller: T::AccountID, id: TaskName) -> DispatchResult {
+		let address = self.lookup(id).ok_or("Scheduled call not found")?;
+		self.do_cancel(&caller, address)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{
+		balances, new_test_runtime, run_to_block, types, Runtime, RuntimeCall, RuntimeEvent,
+	};
+
+	type Scheduler = super::Pallet<Runtime>;
+
+	fn transfer(to: &str, amount: u128) -> Box<RuntimeCall> {
+		let to = to.to_string().into();
+		Box::new(RuntimeCall::balances(balances::Call::transfer { to, amount }))
+	}
+
+	#[test]
+	fn schedule_dispatches_in_priority_order() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 0, None, 0, transfer("bob", 10)),
+			Err("Target block number is in the past")
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 2, None, 10, transfer("bob", 60)),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 2, None, 0, transfer("charlie", 60)),
+			Ok(())
+		);
+
+		run_to_block(&mut runtime, 1);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+
+		// Charlie's transfer has the higher priority, so Bob's transfer fails.
+		run_to_block(&mut runtime, 2);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 60);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 0);
+		assert_eq!(
+			runtime.system.events(),
+			&[
+				RuntimeEvent::scheduler(Event::Dispatched {
+					task: (2, 1),
+					id: None,
+					result: Ok(())
+				}),
+				RuntimeEvent::scheduler(Event::Dispatched {
+					task: (2, 0),
+					id: None,
+					result: Err("Not enough funds")
+				}),
+			]
+		);
+		assert!(runtime.scheduler.agenda(2).is_empty());
+	}
+
+	#[test]
+	fn periodic_and_named_schedules() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			Scheduler::schedule_named(
+				&mut runtime,
+				alice.clone(),
+				"payroll",
+				2,
+				Some((3, 3)),
+				0,
+				transfer("bob", 10)
+			),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule_named(
+				&mut runtime,
+				bob.clone(),
+				"payroll",
+				4,
+				None,
+				0,
+				transfer("bob", 1)
+			),
+			Err("Name is already in use")
+		);
+
+		run_to_block(&mut runtime, 2);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.scheduler.lookup("payroll"), Some((5, 0)));
+
+		run_to_block(&mut runtime, 8);
+		assert_eq!(runtime.balances.balance(&bob), 30);
+		assert_eq!(runtime.scheduler.lookup("payroll"), None);
+
+		// The period has ended, so nothing more is transferred.
+		run_to_block(&mut runtime, 11);
+		assert_eq!(runtime.balances.balance(&bob), 30);
+	}
+
+	#[test]
+	fn cancel_schedules() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 2, None, 0, transfer("bob", 10)),
+			Ok(())
+		);
+		assert_eq!(
+			Scheduler::schedule_named(
+				&mut runtime,
+				alice.clone(),
+				"payroll",
+				2,
+				Some((1, 5)),
+				0,
+				transfer("bob", 10)
+			),
+			Ok(())
+		);
+
+		let scheduler = &mut runtime.scheduler;
+		assert_eq!(scheduler.cancel(bob.clone(), 2, 0), Err("Caller did not schedule the call"));
+		assert_eq!(scheduler.cancel(alice.clone(), 2, 0), Ok(()));
+		assert_eq!(scheduler.cancel(alice.clone(), 2, 0), Err("Scheduled call not found"));
+		assert_eq!(scheduler.cancel_named(alice.clone(), "payroll"), Ok(()));
+		assert_eq!(scheduler.lookup("payroll"), None);
+
+		run_to_block(&mut runtime, 4);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+	}
+
+	#[test]
+	fn agenda_is_limited() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+
+		for _ in 0..<Runtime as super::Config>::MAX_SCHEDULED_PER_BLOCK {
+			assert_eq!(
+				Scheduler::schedule(&mut runtime, alice.clone(), 2, None, 0, transfer("bob", 1)),
+				Ok(())
+			);
+		}
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 2, None, 0, transfer("bob", 1)),
+			Err("Agenda is full")
+		);
+	}
+
+	#[test]
+	fn rejected_block_is_reverted() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let alice = "alice".to_string();
+		assert_eq!(
+			Scheduler::schedule(&mut runtime, alice.clone(), 1, None, 0, transfer("bob", 60)),
+			Ok(())
+		);
+
+		// The scheduled transfer is dispatched, but the block is rejected since it does not set the
+		// timestamp.
+		let block = types::Block {
+			header: types::Header { block_number: 1, author: alice.clone() },
+			extrinsics: Vec::new(),
+		};
+		assert_eq!(
+			runtime.execute_block(block),
+			Err("Timestamp must be updated once in the block")
+		);
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.scheduler.agenda(1).len(), 1);
+
+		run_to_block(&mut runtime, 1);
+		assert_eq!(runtime.balances.balance(&alice), 40);
+	}
+}
@@ -0,0 +1,543 @@
+use crate::{
+	balances::{self, LockIdentifier},
+	support::{self, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use core::cmp::Reverse;
+use num::{
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedRem, CheckedSub},
+	Zero,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The identifier of the lock this pallet places on bonded funds.
+const STAKING_ID: LockIdentifier = "staking";
+
+pub trait Config:
+	system::Config<
+		BlockNumber: CheckedRem,
+		RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>,
+	> + balances::Config<Balance: CheckedMul + CheckedDiv + From<u32>>
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can slash validators.
+	type SlashOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The number of blocks in an era. The validators are elected again at the start of each era.
+	const ERA_LENGTH: Self::BlockNumber;
+	/// The number of eras which unbonded funds stay locked for.
+	const BONDING_DURATION: EraIndex;
+	/// The maximum number of validators elected for an era.
+	const VALIDATOR_COUNT: usize;
+	/// The maximum number of validators an account can nominate.
+	const MAX_NOMINATIONS: usize;
+	/// The maximum number of unbonding chunks an account can have at the same time.
+	const MAX_UNLOCKING_CHUNKS: usize;
+	/// The amount minted at the end of each era, and shared between its validators and their
+	/// nominators.
+	const ERA_REWARD: Self::Balance;
+}
+
+/// The index of an era.
+pub type EraIndex = u32;
+
+/// An amount of funds being unbonded, which can be withdrawn from era `era`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnlockChunk<Balance> {
+	/// The amount being unbonded.
+	pub value: Balance,
+	/// The era at which the funds can be withdrawn.
+	pub era: EraIndex,
+}
+
+/// The bonded funds of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakingLedger<Balance> {
+	/// The total amount locked, including funds being unbonded.
+	pub total: Balance,
+	/// The amount which is actively staked.
+	pub active: Balance,
+	/// The funds being unbonded.
+	pub unlocking: Vec<UnlockChunk<Balance>>,
+}
+
+/// The stake behind an elected validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exposure<AccountID, Balance> {
+	/// The total stake, including the stake of the nominators.
+	pub total: Balance,
+	/// The stake of the validator itself.
+	pub own: Balance,
+	/// The stake of each of the nominators.
+	pub others: Vec<(AccountID, Balance)>,
+}
+
+/// The ledger type used by a runtime `T`.
+pub type StakingLedgerOf<T> = StakingLedger<<T as balances::Config>::Balance>;
+
+/// The exposure type used by a runtime `T`.
+pub type ExposureOf<T> =
+	Exposure<<T as system::Config>::AccountID, <T as balances::Config>::Balance>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// An account bonded `amount`.
+	Bonded { stash: AccountID, amount: Balance },
+	/// An account started to unbond `amount`.
+	Unbonded { stash: AccountID, amount: Balance },
+	/// An account withdrew `amount` of unbonded funds.
+	Withdrawn { stash: AccountID, amount: Balance },
+	/// A new era started, with `validators` elected.
+	EraStarted { era_index: EraIndex, validators: u32 },
+	/// An account was rewarded `amount` for the last era.
+	Rewarded { stash: AccountID, amount: Balance },
+	/// An account was slashed `amount`.
+	Slashed { staker: AccountID, amount: Balance },
+}
+
+/// This is the Staking Module.
+/// It lets accounts bond funds to validate, or to nominate validators. Validators are elected every
+/// era by their stake, and rewards are minted for them and their nominators at the end of the era.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The current era.
+	current_era: EraIndex,
+	/// A map from an account to its bonded funds.
+	ledger: BTreeMap<T::AccountID, StakingLedgerOf<T>>,
+	/// The accounts which want to validate.
+	validators: BTreeSet<T::AccountID>,
+	/// A map from an account to the validators it nominates.
+	nominators: BTreeMap<T::AccountID, Vec<T::AccountID>>,
+	/// A map from the validators elected for the current era to their stake.
+	exposures: BTreeMap<T::AccountID, ExposureOf<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Staking Module.
+	pub fn new() -> Self {
+		Self {
+			current_era: 0,
+			ledger: BTreeMap::new(),
+			validators: BTreeSet::new(),
+			nominators: BTreeMap::new(),
+			exposures: BTreeMap::new(),
+		}
+	}
+
+	/// Get the current era.
+	pub fn current_era(&self) -> EraIndex {
+		self.current_era
+	}
+
+	/// Get the bonded funds of `who`.
+	pub fn ledger(&self, who: &T::AccountID) -> Option<&StakingLedgerOf<T>> {
+		self.ledger.get(who)
+	}
+
+	/// Get the stake behind `validator`, if it is elected for the current era.
+	pub fn exposure(&self, validator: &T::AccountID) -> Option<&ExposureOf<T>> {
+		self.exposures.get(validator)
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Store the ledger of `who`, and update the lock on their funds to match it.
+	fn update_ledger(runtime: &mut T, who: &T::AccountID, ledger: StakingLedgerOf<T>) {
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		balances.set_lock(STAKING_ID, who, ledger.total);
+		let staking = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if ledger.total.is_zero() {
+			staking.ledger.remove(who);
+			staking.validators.remove(who);
+			staking.nominators.remove(who);
+		} else {
+			staking.ledger.insert(who.clone(), ledger);
+		}
+	}
+
+	/// Slash up to `amount` of the bonded funds of `who`, starting with the active funds.
+	/// The slashed funds go to the `balances` pallet's `OnUnbalanced` handler.
+	pub fn slash(runtime: &mut T, who: &T::AccountID, amount: T::Balance) -> T::Balance {
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let mut ledger = match staking.ledger.get(who) {
+			Some(ledger) => ledger.clone(),
+			None => return T::Balance::zero(),
+		};
+
+		let amount = amount.min(ledger.total);
+		let mut remaining = amount;
+		for value in core::iter::once(&mut ledger.active)
+			.chain(ledger.unlocking.iter_mut().map(|chunk| &mut chunk.value))
+		{
+			let slashed = remaining.min(*value);
+			*value = value.checked_sub(&slashed).unwrap_or(T::Balance::zero());
+			remaining = remaining.checked_sub(&slashed).unwrap_or(T::Balance::zero());
+		}
+		ledger.unlocking.retain(|chunk| !chunk.value.is_zero());
+		ledger.total = ledger.total.checked_sub(&amount).unwrap_or(T::Balance::zero());
+
+		Self::update_ledger(runtime, who, ledger);
+		let slashed = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).slash(who, amount);
+		if !slashed.is_zero() {
+			Self::deposit_event(runtime, Event::Slashed { staker: who.clone(), amount: slashed });
+		}
+		slashed
+	}
+
+	/// Slash `percent` of the stake behind `validator` in the current era, including the stake of
+	/// its nominators.
+	pub fn slash_validator(runtime: &mut T, validator: &T::AccountID, percent: u8) {
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let exposure = match staking.exposures.get(validator) {
+			Some(exposure) => exposure.clone(),
+			None => return,
+		};
+		let percent = T::Balance::from(percent.min(100) as u32);
+		let stakers = core::iter::once((validator.clone(), exposure.own)).chain(exposure.others);
+		for (who, stake) in stakers {
+			let amount = stake
+				.checked_mul(&percent)
+				.and_then(|amount| amount.checked_div(&T::Balance::from(100)))
+				.unwrap_or(stake);
+			Self::slash(runtime, &who, amount);
+		}
+	}
+
+	/// Elect the validators with the most stake, and store their exposures for the new era. The
+	/// stake of each nominator is split evenly between the validators it nominates.
+	fn elect(&mut self) {
+		let mut exposures = self
+			.validators
+			.iter()
+			.filter_map(|validator| {
+				let own = self.ledger.get(validator)?.active;
+				Some((validator.clone(), Exposure { total: own, own, others: Vec::new() }))
+			})
+			.collect::<BTreeMap<_, _>>();
+
+		for (nominator, targets) in &self.nominators {
+			let targets = targets
+				.iter()
+				.filter(|target| exposures.contains_key(*target))
+				.collect::<Vec<_>>();
+			let active = match self.ledger.get(nominator) {
+				Some(ledger) if !targets.is_empty() => ledger.active,
+				_ => continue,
+			};
+			let share = active
+				.checked_div(&T::Balance::from(targets.len() as u32))
+				.unwrap_or(T::Balance::zero());
+			for target in targets {
+				if let Some(exposure) = exposures.get_mut(target) {
+					exposure.total = exposure.total.checked_add(&share).unwrap_or(exposure.total);
+					exposure.others.push((nominator.clone(), share));
+				}
+			}
+		}
+
+		let mut elected = exposures
+			.into_iter()
+			.filter(|(_, exposure)| !exposure.total.is_zero())
+			.collect::<Vec<_>>();
+		// The sort is stable, so validators with the same stake are elected in account order.
+		elected.sort_by_key(|(_, exposure)| Reverse(exposure.total));
+		elected.truncate(T::VALIDATOR_COUNT);
+		self.exposures = elected.into_iter().collect();
+	}
+
+	/// Mint the `ERA_REWARD`, split evenly between the validators of the current era. The reward of
+	/// each validator is shared with its nominators in proportion to their stake.
+	fn reward_validators(runtime: &mut T) {
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let exposures = staking.exposures.clone();
+		let count = T::Balance::from(exposures.len() as u32);
+		let per_validator = match T::ERA_REWARD.checked_div(&count) {
+			Some(per_validator) => per_validator,
+			None => return,
+		};
+
+		for (validator, exposure) in exposures {
+			let stakers = core::iter::once((validator, exposure.own)).chain(exposure.others);
+			for (who, stake) in stakers {
+				let amount = per_validator
+					.checked_mul(&stake)
+					.and_then(|amount| amount.checked_div(&exposure.total))
+					.unwrap_or(T::Balance::zero());
+				if amount.is_zero() {
+					continue;
+				}
+				// If the balance of `who` would overflow, they simply do not get a reward.
+				let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+				if balances.deposit(&who, amount).is_ok() {
+					Self::deposit_event(runtime, Event::Rewarded { stash: who, amount });
+				}
+			}
+		}
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Every `ERA_LENGTH` blocks, reward the validators of the current era, and start a new era
+	/// with newly elected validators.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		if !n.checked_rem(&T::ERA_LENGTH).is_some_and(|rem| rem.is_zero()) {
+			return Ok(());
+		}
+		Self::reward_validators(runtime);
+
+		let staking = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		staking.current_era = staking.current_era.checked_add(1).ok_or("Overflow error")?;
+		staking.elect();
+		let event = Event::EraStarted {
+			era_index: staking.current_era,
+			validators: staking.exposures.len() as u32,
+		};
+		Self::deposit_event(runtime, event);
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Bond `value` of the balance of the `caller`, adding to any funds they already bonded.
+	/// Bonded funds are locked until they are unbonded and withdrawn.
+	pub fn bond(runtime: &mut T, caller: T::AccountID, value: T::Balance) -> DispatchResult {
+		if value.is_zero() {
+			return Err("Cannot bond zero");
+		}
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let mut ledger = staking.ledger.get(&caller).cloned().unwrap_or(StakingLedger {
+			total: T::Balance::zero(),
+			active: T::Balance::zero(),
+			unlocking: Vec::new(),
+		});
+		ledger.total = ledger.total.checked_add(&value).ok_or("Balance overflow error")?;
+		ledger.active = ledger.active.checked_add(&value).ok_or("Balance overflow error")?;
+		// Other locks overlap with the staking lock, so only the total balance matters.
+		if ledger.total > GetPallet::<balances::Pallet<T>>::pallet(runtime).balance(&caller) {
+			return Err("Not enough funds");
+		}
+
+		Self::update_ledger(runtime, &caller, ledger);
+		Self::deposit_event(runtime, Event::Bonded { stash: caller, amount: value });
+		Ok(())
+	}
+
+	/// Start unbonding up to `value` of the active funds of the `caller`. The funds can be
+	/// withdrawn after `BONDING_DURATION` eras.
+	pub fn unbond(runtime: &mut T, caller: T::AccountID, value: T::Balance) -> DispatchResult {
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let mut ledger = staking.ledger.get(&caller).cloned().ok_or("Not bonded")?;
+		if ledger.unlocking.len() >= T::MAX_UNLOCKING_CHUNKS {
+			return Err("Too many unlocking chunks");
+		}
+		let value = value.min(ledger.active);
+		if value.is_zero() {
+			return Err("Nothing to unbond");
+		}
+		let era = staking.current_era.checked_add(T::BONDING_DURATION).ok_or("Overflow error")?;
+		ledger.active = ledger.active.checked_sub(&value).ok_or("Not enough funds")?;
+		ledger.unlocking.push(UnlockChunk { value, era });
+
+		Self::update_ledger(runtime, &caller, ledger);
+		Self::deposit_event(runtime, Event::Unbonded { stash: caller, amount: value });
+		Ok(())
+	}
+
+	/// Withdraw the funds of the `caller` which have finished unbonding, unlocking them.
+	pub fn withdraw_unbonded(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		let staking = GetPallet::<Pallet<T>>::pallet(runtime);
+		let current_era = staking.current_era;
+		let mut ledger = staking.ledger.get(&caller).cloned().ok_or("Not bonded")?;
+		let mut amount = T::Balance::zero();
+		for chunk in ledger.unlocking.iter().filter(|chunk| chunk.era <= current_era) {
+			amount = amount.checked_add(&chunk.value).ok_or("Balance overflow error")?;
+		}
+		ledger.unlocking.retain(|chunk| chunk.era > current_era);
+		ledger.total = ledger.total.checked_sub(&amount).ok_or("Not enough funds")?;
+
+		Self::update_ledger(runtime, &caller, ledger);
+		Self::deposit_event(runtime, Event::Withdrawn { stash: caller, amount });
+		Ok(())
+	}
+
+	/// Declare the `caller` wants to validate, from the next election. This stops any nomination.
+	pub fn validate(&mut self, caller: T::AccountID) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("Not bonded");
+		}
+		self.nominators.remove(&caller);
+		self.validators.insert(caller);
+		Ok(())
+	}
+
+	/// Declare the `caller` wants to nominate the `targets`, from the next election. This stops
+	/// any intention to validate.
+	pub fn nominate(&mut self, caller: T::AccountID, targets: Vec<T::AccountID>) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("Not bonded");
+		}
+		if targets.is_empty() {
+			return Err("No targets");
+		}
+		if targets.len() > T::MAX_NOMINATIONS {
+			return Err("Too many targets");
+		}
+		self.validators.remove(&caller);
+		self.nominators.insert(caller, targets);
+		Ok(())
+	}
+
+	/// Declare the `caller` no longer wants to validate or nominate, from the next election.
+	pub fn chill(&mut self, caller: T::AccountID) -> DispatchResult {
+		if !self.ledger.contains_key(&caller) {
+			return Err("Not bonded");
+		}
+		self.validators.remove(&caller);
+		self.nominators.remove(&caller);
+		Ok(())
+	}
+
+	/// Slash `percent` of the stake behind `validator` in the current era.
+	/// Only the `SlashOrigin` can slash validators.
+	pub fn force_slash(
+		runtime: &mut T,
+		caller: T::AccountID,
+		validator: T::AccountID,
+		percent: u8,
+	) -> DispatchResult {
+		T::SlashOrigin::ensure_origin(runtime, &caller)?;
+		Self::slash_validator(runtime, &validator, percent);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Event, UnlockChunk};
+	use crate::{new_test_runtime, run_to_block, support, treasury, Runtime, RuntimeEvent};
+
+	type Staking = super::Pallet<Runtime>;
+
+	#[test]
+	fn bond_and_unbond() {
+		let mut runtime = new_test_runtime(&[
+			("alice", 1_000),
+			("bob", 1_000),
+			("charlie", 1_000),
+			("dave", 1_000),
+		]);
+		let alice = "alice".to_string();
+
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 2_000), Err("Not enough funds"));
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 600), Ok(()));
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 100), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 700);
+
+		assert_eq!(Staking::unbond(&mut runtime, alice.clone(), 300), Ok(()));
+		let ledger = runtime.staking.ledger(&alice).unwrap();
+		assert_eq!(ledger.active, 400);
+		assert_eq!(ledger.unlocking, vec![UnlockChunk { value: 300, era: 2 }]);
+
+		// The unbonded funds stay locked for `BONDING_DURATION` eras.
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 700);
+		run_to_block(&mut runtime, 10);
+		assert_eq!(runtime.staking.current_era(), 2);
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 400);
+
+		assert_eq!(Staking::unbond(&mut runtime, alice.clone(), 1_000), Ok(()));
+		run_to_block(&mut runtime, 20);
+		assert_eq!(Staking::withdraw_unbonded(&mut runtime, alice.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&alice), 0);
+		assert!(runtime.staking.ledger(&alice).is_none());
+	}
+
+	#[test]
+	fn election_and_rewards() {
+		let mut runtime = new_test_runtime(&[
+			("alice", 1_000),
+			("bob", 1_000),
+			("charlie", 1_000),
+			("dave", 1_000),
+		]);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let (charlie, dave) = ("charlie".to_string(), "dave".to_string());
+
+		assert_eq!(runtime.staking.validate(alice.clone()), Err("Not bonded"));
+		for (who, value) in [(&alice, 300), (&bob, 100), (&charlie, 200), (&dave, 400)] {
+			assert_eq!(Staking::bond(&mut runtime, who.clone(), value), Ok(()));
+		}
+		assert_eq!(runtime.staking.validate(alice.clone()), Ok(()));
+		assert_eq!(runtime.staking.validate(bob.clone()), Ok(()));
+		assert_eq!(runtime.staking.validate(charlie.clone()), Ok(()));
+		// Dave's stake is split between alice and bob.
+		let targets = vec![alice.clone(), bob.clone()];
+		assert_eq!(runtime.staking.nominate(dave.clone(), targets), Ok(()));
+
+		// Only the two validators with the most stake are elected.
+		run_to_block(&mut runtime, 5);
+		let exposure = runtime.staking.exposure(&alice).unwrap();
+		assert_eq!(exposure.total, 500);
+		assert_eq!(exposure.others, vec![(dave.clone(), 200)]);
+		assert_eq!(runtime.staking.exposure(&bob).unwrap().total, 300);
+		assert!(runtime.staking.exposure(&charlie).is_none());
+
+		// Each validator gets half of the reward, shared with dave in proportion to the stake.
+		run_to_block(&mut runtime, 10);
+		assert_eq!(runtime.balances.balance(&alice), 1_030);
+		assert_eq!(runtime.balances.balance(&bob), 1_016);
+		assert_eq!(runtime.balances.balance(&charlie), 1_000);
+		assert_eq!(runtime.balances.balance(&dave), 1_053);
+		assert_eq!(
+			runtime.system.events().last(),
+			Some(&RuntimeEvent::staking(Event::EraStarted { era_index: 2, validators: 2 }))
+		);
+	}
+
+	#[test]
+	fn slashing() {
+		let mut runtime = new_test_runtime(&[
+			("alice", 1_000),
+			("bob", 1_000),
+			("charlie", 1_000),
+			("dave", 1_000),
+		]);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let root = support::root_account::<String>();
+
+		assert_eq!(Staking::bond(&mut runtime, alice.clone(), 500), Ok(()));
+		assert_eq!(Staking::bond(&mut runtime, bob.clone(), 200), Ok(()));
+		assert_eq!(runtime.staking.validate(alice.clone()), Ok(()));
+		assert_eq!(runtime.staking.nominate(bob.clone(), vec![alice.clone()]), Ok(()));
+		assert_eq!(Staking::unbond(&mut runtime, alice.clone(), 300), Ok(()));
+		run_to_block(&mut runtime, 5);
+
+		assert_eq!(
+			Staking::force_slash(&mut runtime, alice.clone(), alice.clone(), 50),
+			Err("Bad origin")
+		);
+		assert_eq!(Staking::force_slash(&mut runtime, root.clone(), alice.clone(), 50), Ok(()));
+
+		// Both alice and bob lose half of their stake behind alice.
+		assert_eq!(runtime.staking.ledger(&alice).unwrap().active, 100);
+		assert_eq!(runtime.staking.ledger(&bob).unwrap().active, 100);
+		assert_eq!(runtime.balances.balance(&alice), 900);
+		assert_eq!(runtime.balances.balance(&bob), 900);
+		// The slashed funds go to the treasury.
+		assert_eq!(treasury::Pallet::<Runtime>::pot(&runtime), 200);
+
+		// The active funds are slashed first, then the funds being unbonded.
+		assert_eq!(Staking::slash(&mut runtime, &alice, 150), 150);
+		let ledger = runtime.staking.ledger(&alice).unwrap();
+		assert_eq!((ledger.total, ledger.active), (250, 0));
+		assert_eq!(ledger.unlocking, vec![UnlockChunk { value: 250, era: 2 }]);
+		assert_eq!(runtime.balances.balance(&alice), 750);
+		assert_eq!(treasury::Pallet::<Runtime>::pot(&runtime), 350);
+	}
+}
@@ -1,4 +1,8 @@
+use core::marker::PhantomData;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 /// The most primitive representation of a Blockchain block.
+#[derive(Hash)]
 pub struct Block<Header, Extrinsic> {
 	/// The block header contains metadata about the block.
 	pub header: Header,
@@ -6,21 +10,28 @@ pub struct Block<Header, Extrinsic> {
 	pub extrinsics: Vec<Extrinsic>,
 }
 
-/// We are using an extremely simplified header which only contains the current block number.
+/// We are using an extremely simplified header which only contains the current block number and
+/// the account which authored the block.
 /// On a real blockchain, you would expect to also find:
 /// - parent block hash
 /// - state root
 /// - extrinsics root
 /// - etc...
-pub struct Header<BlockNumber> {
+#[derive(Hash)]
+pub struct Header<BlockNumber, AccountID> {
 	pub block_number: BlockNumber,
+	/// The account which authored the block. The runtime records it, so that pallets can check
+	/// that it was allowed to author the block.
+	pub author: AccountID,
 }
 
 /// This is an "extrinsic": literally an external message from outside of the blockchain.
 /// This simplified version of an extrinsic tells us who is making the call, and which call they are
-/// making.
+/// making. Extrinsics without a `caller` are unsigned, like the inherents included by the block
+/// author, and can only make calls marked `#[unsigned]`.
+#[derive(Hash)]
 pub struct Extrinsic<Caller, Call> {
-	pub caller: Caller,
+	pub caller: Option<Caller>,
 	pub call: Call,
 }
 
@@ -39,4 +50,226 @@ pub trait Dispatch {
 	/// This function takes a `caller` and the `call` they want to make, and returns a `Result`
 	/// based on the outcome of that function call.
 	fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult;
+
+	/// This function dispatches a `call` from an unsigned extrinsic, which has no caller.
+	fn dispatch_unsigned(&mut self, call: Self::Call) -> DispatchResult;
+}
+
+/// A trait which gives access to a pallet `P` stored in the runtime.
+///
+/// The `runtime` macro implements this for every pallet in the `Runtime` struct. Pallets which
+/// depend on the state of other pallets require it in their `Config`, and reach those pallets
+/// through the `runtime: &mut T` argument of their calls.
+pub trait GetPallet<P> {
+	/// Get a reference to the pallet `P`.
+	fn pallet(&self) -> &P;
+	/// Get a mutable reference to the pallet `P`.
+	fn pallet_mut(&mut self) -> &mut P;
+}
+
+/// A trait for converting a value of type `A` into type `B`, for example to use a block number as
+/// a balance.
+pub trait Convert<A, B> {
+	/// Convert `a` into a value of type `B`.
+	fn convert(a: A) -> B;
+}
+
+/// A `Convert` implementation which simply uses the `Into` trait.
+pub struct ConvertInto;
+
+impl<A: Into<B>, B> Convert<A, B> for ConvertInto {
+	fn convert(a: A) -> B {
+		a.into()
+	}
+}
+
+/// The hash of a call, used by pallets which refer to a call without storing it.
+pub type CallHash = u64;
+
+/// The hash of a block. The system pallet chains each block to its parent by hashing them
+/// together.
+pub type BlockHash = u64;
+
+/// Hash some value, such as a call, into a `CallHash`.
+pub fn hash_of<H: Hash>(value: &H) -> CallHash {
+	let mut hasher = DefaultHasher::new();
+	value.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// A trait for account types which can be deterministically derived from a seed. This is used for
+/// accounts which are controlled by a pallet rather than a key, such as multisig accounts.
+pub trait DeriveAccount {
+	/// Derive an account from the given `seed`.
+	fn derive_account<S: Hash>(seed: &S) -> Self;
+}
+
+impl DeriveAccount for String {
+	fn derive_account<S: Hash>(seed: &S) -> Self {
+		format!("0x{:016x}", hash_of(seed))
+	}
+}
+
+/// Get the root account, which is allowed to make privileged calls.
+/// No key controls it, so calls can only be made from it by the runtime itself.
+pub fn root_account<AccountID: DeriveAccount>() -> AccountID {
+	AccountID::derive_account(&"root")
+}
+
+/// A trait for looking up the account a `Source`, like a short account index, refers to, given
+/// access to the whole `Runtime`. Calls take a `Source` rather than a full account where keeping
+/// extrinsics small matters.
+pub trait Lookup<Runtime> {
+	/// The type referring to an account.
+	type Source;
+	/// The type of the account referred to.
+	type Target;
+	/// Get the account `source` refers to.
+	fn lookup(runtime: &Runtime, source: Self::Source) -> Result<Self::Target, &'static str>;
+}
+
+/// A `Lookup` implementation where an account only refers to itself.
+#[allow(unused)]
+pub struct IdentityLookup<AccountID>(PhantomData<AccountID>);
+
+impl<Runtime, AccountID> Lookup<Runtime> for IdentityLookup<AccountID> {
+	type Source = AccountID;
+	type Target = AccountID;
+
+	fn lookup(_runtime: &Runtime, source: AccountID) -> Result<AccountID, &'static str> {
+		Ok(source)
+	}
+}
+
+/// A reference to an account, either by its full `Id` or by a short `Index`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MultiAddress<AccountID, AccountIndex> {
+	/// The account itself.
+	Id(AccountID),
+	/// An index which was claimed by the account.
+	Index(AccountIndex),
+}
+
+impl<AccountID, AccountIndex> From<AccountID> for MultiAddress<AccountID, AccountIndex> {
+	fn from(who: AccountID) -> Self {
+		Self::Id(who)
+	}
+}
+
+/// A trait for checking that the caller of a privileged call is allowed to make it, given the
+/// state of the `Runtime`.
+pub trait EnsureOrigin<Runtime, AccountID> {
+	/// Return an error if `caller` is not allowed to make the call.
+	fn ensure_origin(runtime: &Runtime, caller: &AccountID) -> DispatchResult;
+}
+
+/// An `EnsureOrigin` implementation which only allows the root account.
+pub struct EnsureRoot;
+
+impl<Runtime, AccountID> EnsureOrigin<Runtime, AccountID> for EnsureRoot
+where
+	AccountID: DeriveAccount + PartialEq,
+{
+	fn ensure_origin(_runtime: &Runtime, caller: &AccountID) -> DispatchResult {
+		if caller != &root_account::<AccountID>() {
+			return Err("Bad origin");
+		}
+		Ok(())
+	}
+}
+
+/// An `EnsureOrigin` implementation which allows the callers allowed by either `L` or `R`.
+pub struct EitherOf<L, R>(PhantomData<(L, R)>);
+
+impl<Runtime, AccountID, L, R> EnsureOrigin<Runtime, AccountID> for EitherOf<L, R>
+where
+	L: EnsureOrigin<Runtime, AccountID>,
+	R: EnsureOrigin<Runtime, AccountID>,
+{
+	fn ensure_origin(runtime: &Runtime, caller: &AccountID) -> DispatchResult {
+		L::ensure_origin(runtime, caller).or_else(|_| R::ensure_origin(runtime, caller))
+	}
+}
+
+/// Execute `f` on the `runtime`, reverting all of its changes if it returns an error.
+///
+/// This simply takes a snapshot of the whole runtime beforehand, which is fine for our small state
+/// machine.
+pub fn with_transaction<R: Clone>(
+	runtime: &mut R,
+	f: impl FnOnce(&mut R) -> DispatchResult,
+) -> DispatchResult {
+	let snapshot = runtime.clone();
+	let result = f(runtime);
+	if result.is_err() {
+		*runtime = snapshot;
+	}
+	result
+}
+
+/// A trait for filtering the calls which an instance of some type, like a kind of proxy, is
+/// allowed to make.
+pub trait InstanceFilter<Call> {
+	/// Check whether `call` is allowed by this instance.
+	fn filter(&self, call: &Call) -> bool;
+}
+
+/// A source of on-chain randomness, given access to the whole `Runtime`.
+///
+/// On-chain randomness is derived from data the block authors control, so an author can bias it,
+/// for example by not publishing a block whose outcome it dislikes. Implementations should only be
+/// used where such a bias is acceptable, and never for high-stakes outcomes.
+pub trait Randomness<Runtime, Output, BlockNumber> {
+	/// Get a random value for `subject`, which should be different for every use of the
+	/// randomness, along with a block number. The value could be known from that block on, so it
+	/// must only be used to decide on commitments made before that block.
+	fn random(runtime: &Runtime, subject: &[u8]) -> (Output, BlockNumber);
+}
+
+/// A trait for pallets which keep a set of members maintained by another pallet, like the
+/// membership pallet, and need to be notified when it changes.
+pub trait ChangeMembers<Runtime, AccountID: Clone + Ord> {
+	/// Called when the members change, with the `incoming` and `outgoing` members and the new set
+	/// of members. All of them are sorted.
+	fn change_members_sorted(
+		runtime: &mut Runtime,
+		incoming: &[AccountID],
+		outgoing: &[AccountID],
+		sorted_new: &[AccountID],
+	);
+
+	/// Called when the members change from `old_members` to `new_members`, both sorted. The
+	/// incoming and outgoing members are computed from them.
+	fn set_members_sorted(
+		runtime: &mut Runtime,
+		new_members: &[AccountID],
+		old_members: &[AccountID],
+	) {
+		let incoming: Vec<_> = new_members
+			.iter()
+			.filter(|who| old_members.binary_search(who).is_err())
+			.cloned()
+			.collect();
+		let outgoing: Vec<_> = old_members
+			.iter()
+			.filter(|who| new_members.binary_search(who).is_err())
+			.cloned()
+			.collect();
+		Self::change_members_sorted(runtime, &incoming, &outgoing, new_members);
+	}
+}
+
+/// A trait for pallets which execute some logic at the start or end of every block, given access
+/// to the whole `Runtime`. Both hooks do nothing by default. A hook which returns an error makes
+/// the whole block invalid.
+pub trait Hooks<Runtime, BlockNumber> {
+	/// Called at the start of block `n`, before any extrinsic is executed.
+	fn on_initialize(_runtime: &mut Runtime, _n: BlockNumber) -> DispatchResult {
+		Ok(())
+	}
+
+	/// Called at the end of block `n`, after all extrinsics have been executed.
+	fn on_finalize(_runtime: &mut Runtime, _n: BlockNumber) -> DispatchResult {
+		Ok(())
+	}
 }
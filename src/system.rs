@@ -1,27 +1,48 @@
-use core::ops::AddAssign;
-use num::{traits::CheckedAdd, One, Zero};
+use crate::support::BlockHash;
+use core::{fmt::Debug, ops::AddAssign};
+use num::{
+	traits::{CheckedAdd, CheckedSub},
+	One, Zero,
+};
 use std::collections::BTreeMap;
 
 pub trait Config {
-	type AccountID: Ord + Clone;
-	type BlockNumber: CheckedAdd + One + Zero + Copy + AddAssign;
+	type AccountID: Ord + Clone + Debug;
+	type BlockNumber: CheckedAdd + CheckedSub + One + Zero + Copy + Ord + AddAssign + Debug;
 	type Nonce: CheckedAdd + One + Zero + Copy;
+	/// The overarching event type, which the events of every pallet can be converted into.
+	type RuntimeEvent;
 }
 
 /// This is the System Pallet.
 /// It handles low level state needed for your blockchain.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Pallet<T: Config> {
 	/// The current block number.
 	block_number: T::BlockNumber,
+	/// The hash of the previous block, or zero before the first block.
+	parent_hash: BlockHash,
+	/// The author of the current block, if a block is being executed or was executed.
+	block_author: Option<T::AccountID>,
 	/// A map from an account to their nonce.
 	nonce: BTreeMap<T::AccountID, T::Nonce>,
+	/// The index of the extrinsic currently being executed in the block, if any.
+	extrinsic_index: Option<u32>,
+	/// The events deposited by the pallets in the current block.
+	events: Vec<T::RuntimeEvent>,
 }
 
 impl<T: Config> Pallet<T> {
 	/// Create a new instance of the System Pallet.
 	pub fn new() -> Self {
-		Self { block_number: T::BlockNumber::zero(), nonce: BTreeMap::new() }
+		Self {
+			block_number: T::BlockNumber::zero(),
+			parent_hash: 0,
+			block_author: None,
+			nonce: BTreeMap::new(),
+			extrinsic_index: None,
+			events: Vec::new(),
+		}
 	}
 
 	/// Get the current block number.
@@ -29,6 +50,26 @@ impl<T: Config> Pallet<T> {
 		self.block_number
 	}
 
+	/// Get the hash of the previous block.
+	pub fn parent_hash(&self) -> BlockHash {
+		self.parent_hash
+	}
+
+	/// Record the hash of the block which was just executed, as the parent of the next one.
+	pub fn set_parent_hash(&mut self, hash: BlockHash) {
+		self.parent_hash = hash;
+	}
+
+	/// Get the author of the current block.
+	pub fn block_author(&self) -> Option<&T::AccountID> {
+		self.block_author.as_ref()
+	}
+
+	/// Record the author of the block which is being executed.
+	pub fn set_block_author(&mut self, author: T::AccountID) {
+		self.block_author = Some(author);
+	}
+
 	// This function can be used to increment the block number.
 	// Increases the block number by one.
 	pub fn inc_block_number(&mut self) {
@@ -38,11 +79,38 @@ impl<T: Config> Pallet<T> {
 	// Increment the nonce of an account. This helps us keep track of how many transactions each
 	// account has made.
 	pub fn inc_nonce(&mut self, who: &T::AccountID) {
-		let prev_nonce = *self.nonce.get(&who).unwrap_or(&T::Nonce::zero());
+		let prev_nonce = *self.nonce.get(who).unwrap_or(&T::Nonce::zero());
 		let new_nonce = prev_nonce + T::Nonce::one();
 		self.nonce.insert(who.clone(), new_nonce);
 	}
 
+	/// Get the index of the extrinsic currently being executed in the block, if any.
+	pub fn extrinsic_index(&self) -> Option<u32> {
+		self.extrinsic_index
+	}
+
+	/// Set the index of the extrinsic currently being executed in the block, or `None` when no
+	/// extrinsic is being executed.
+	pub fn set_extrinsic_index(&mut self, index: Option<u32>) {
+		self.extrinsic_index = index;
+	}
+
+	/// Deposit an event, so that it can be seen by anyone observing the chain.
+	pub fn deposit_event(&mut self, event: impl Into<T::RuntimeEvent>) {
+		self.events.push(event.into());
+	}
+
+	/// Get the events deposited in the current block.
+	#[allow(unused)]
+	pub fn events(&self) -> &[T::RuntimeEvent] {
+		&self.events
+	}
+
+	/// Clear the events of the previous block.
+	pub fn reset_events(&mut self) {
+		self.events.clear();
+	}
+
 	#[allow(unused)]
 	pub fn get_nonce(&self, who: T::AccountID) -> T::Nonce {
 		*self.nonce.get(&who).unwrap_or(&T::Nonce::zero())
@@ -57,6 +125,7 @@ mod test {
 		type AccountID = String;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type RuntimeEvent = ();
 	}
 
 	#[test]
@@ -77,7 +146,7 @@ mod test {
 
 		while i > 0 {
 			new_system.inc_block_number();
-			i = i - 1;
+			i -= 1;
 		}
 
 		assert_eq!(new_system.block_number(), 6);
@@ -0,0 +1,215 @@
+use crate::{
+	support::{self, DispatchResult, GetPallet},
+	system,
+};
+use core::fmt::Debug;
+use num::traits::{CheckedAdd, Zero};
+
+pub trait Config: system::Config + Sized + GetPallet<Pallet<Self>> {
+	/// The type used to represent time, like the number of milliseconds since the unix epoch.
+	type Moment: Zero + CheckedAdd + Copy + Ord + Debug;
+	/// The minimum time which must pass between the timestamps of two blocks.
+	const MINIMUM_PERIOD: Self::Moment;
+}
+
+/// This is the Timestamp Module.
+/// It keeps track of the current time, which the block author sets with an unsigned inherent which
+/// must be included exactly once in every block.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The timestamp of the current block, or `None` if it was never set.
+	now: Option<T::Moment>,
+	/// The number of times the timestamp has been set in the current block.
+	updates: u32,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Timestamp Module.
+	pub fn new() -> Self {
+		Self { now: None, updates: 0 }
+	}
+
+	/// Get the timestamp of the current block, or zero if it was never set.
+	pub fn now(&self) -> T::Moment {
+		self.now.unwrap_or_else(T::Moment::zero)
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Make sure that the timestamp was set exactly once during the block, and get ready for the
+	/// next block.
+	fn on_finalize(runtime: &mut T, _n: T::BlockNumber) -> DispatchResult {
+		let timestamp = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if core::mem::take(&mut timestamp.updates) != 1 {
+			return Err("Timestamp must be updated once in the block");
+		}
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Set the timestamp of the current block to `now`.
+	/// This is an inherent, which must be included exactly once in every block, and must move the
+	/// time forward by at least `MINIMUM_PERIOD`, unless it is the first time it is set.
+	#[unsigned]
+	pub fn set(&mut self, now: T::Moment) -> DispatchResult {
+		// A second update is counted too, so that `on_finalize` rejects the block.
+		if self.updates > 0 {
+			self.updates = self.updates.saturating_add(1);
+			return Err("Timestamp must be updated only once in the block");
+		}
+
+		if let Some(last) = self.now {
+			let earliest = last.checked_add(&T::MINIMUM_PERIOD).ok_or("Overflow error")?;
+			if now < earliest {
+				return Err("Timestamp must increment by at least MINIMUM_PERIOD");
+			}
+		}
+
+		self.now = Some(now);
+		self.updates = 1;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		balances,
+		support::{Dispatch, Hooks},
+		types, Runtime, RuntimeCall,
+	};
+
+	type Timestamp = super::Pallet<Runtime>;
+
+	fn set_call(now: u64) -> RuntimeCall {
+		RuntimeCall::timestamp(super::Call::set { now })
+	}
+
+	#[test]
+	fn set_timestamp() {
+		let mut runtime = Runtime::new();
+		assert_eq!(runtime.timestamp.now(), 0);
+
+		assert_eq!(runtime.timestamp.set(1_000), Ok(()));
+		assert_eq!(runtime.timestamp.now(), 1_000);
+		assert_eq!(
+			runtime.timestamp.set(10_000),
+			Err("Timestamp must be updated only once in the block")
+		);
+		assert_eq!(
+			Timestamp::on_finalize(&mut runtime, 1),
+			Err("Timestamp must be updated once in the block")
+		);
+
+		// The next timestamp must be at least `MINIMUM_PERIOD` later.
+		assert_eq!(
+			runtime.timestamp.set(3_999),
+			Err("Timestamp must increment by at least MINIMUM_PERIOD")
+		);
+		assert_eq!(runtime.timestamp.set(4_000), Ok(()));
+		assert_eq!(runtime.timestamp.now(), 4_000);
+		assert_eq!(Timestamp::on_finalize(&mut runtime, 2), Ok(()));
+	}
+
+	#[test]
+	fn zero_timestamp_is_checked() {
+		let mut runtime = Runtime::new();
+
+		// A first timestamp of zero still counts as set, so the next one must move time forward.
+		assert_eq!(runtime.timestamp.set(0), Ok(()));
+		assert_eq!(Timestamp::on_finalize(&mut runtime, 1), Ok(()));
+		assert_eq!(
+			runtime.timestamp.set(0),
+			Err("Timestamp must increment by at least MINIMUM_PERIOD")
+		);
+		assert_eq!(runtime.timestamp.set(3_000), Ok(()));
+	}
+
+	#[test]
+	fn timestamp_is_mandatory() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+
+		let header = types::Header { block_number: 1, author: alice.clone() };
+		let block = types::Block { header, extrinsics: vec![] };
+		assert_eq!(
+			runtime.execute_block(block),
+			Err("Timestamp must be updated once in the block")
+		);
+
+		// The timestamp cannot be set by a signed extrinsic.
+		let block = types::Block {
+			header: types::Header { block_number: 1, author: alice.clone() },
+			extrinsics: vec![types::Extrinsic {
+				caller: Some(alice.clone()),
+				call: set_call(6_000),
+			}],
+		};
+		assert_eq!(
+			runtime.execute_block(block),
+			Err("Timestamp must be updated once in the block")
+		);
+		assert_eq!(runtime.timestamp.now(), 0);
+
+		// Signed calls cannot be made by an unsigned extrinsic either.
+		let to = alice.clone().into();
+		let call = RuntimeCall::balances(balances::Call::transfer { to, amount: 0 });
+		assert_eq!(runtime.dispatch_unsigned(call), Err("Call must be signed"));
+
+		let block = types::Block {
+			header: types::Header { block_number: 1, author: alice.clone() },
+			extrinsics: vec![types::Extrinsic { caller: None, call: set_call(6_000) }],
+		};
+		assert_eq!(runtime.execute_block(block), Ok(()));
+		assert_eq!(runtime.timestamp.now(), 6_000);
+		// Unsigned extrinsics do not touch any nonce.
+		assert_eq!(runtime.system.get_nonce(alice), 0);
+	}
+
+	#[test]
+	fn timestamp_is_set_once_per_block() {
+		let mut runtime = Runtime::new();
+		let block = types::Block {
+			header: types::Header { block_number: 1, author: "alice".to_string() },
+			extrinsics: vec![
+				types::Extrinsic { caller: None, call: set_call(6_000) },
+				types::Extrinsic { caller: None, call: set_call(12_000) },
+			],
+		};
+		assert_eq!(
+			runtime.execute_block(block),
+			Err("Timestamp must be updated once in the block")
+		);
+		assert_eq!(runtime.timestamp.now(), 0);
+	}
+
+	#[test]
+	fn rejected_block_is_reverted() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		// The transfer succeeds, but the block is rejected since it does not set the timestamp.
+		let transfer = balances::Call::transfer { to: bob.clone().into(), amount: 50 };
+		let block = types::Block {
+			header: types::Header { block_number: 1, author: bob.clone() },
+			extrinsics: vec![types::Extrinsic {
+				caller: Some(alice.clone()),
+				call: RuntimeCall::balances(transfer),
+			}],
+		};
+		assert_eq!(
+			runtime.execute_block(block),
+			Err("Timestamp must be updated once in the block")
+		);
+
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.system.parent_hash(), 0);
+		assert_eq!(runtime.system.get_nonce(alice.clone()), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+	}
+}
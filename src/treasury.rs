@@ -0,0 +1,327 @@
+use crate::{
+	balances,
+	support::{self, DeriveAccount, DispatchResult, EnsureOrigin, GetPallet},
+	system,
+};
+use num::{
+	traits::{CheckedRem, CheckedSub},
+	Zero,
+};
+use std::collections::BTreeMap;
+
+pub trait Config:
+	system::Config<
+		AccountID: DeriveAccount,
+		BlockNumber: CheckedRem,
+		RuntimeEvent: From<Event<Self::AccountID, Self::Balance>>,
+	> + balances::Config
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// The origin which can approve or reject spend proposals.
+	type ApproveOrigin: EnsureOrigin<Self, Self::AccountID>;
+	/// The deposit which is reserved from the proposer of a spend. It is returned if the proposal
+	/// is paid out, and slashed if it is rejected.
+	const PROPOSAL_BOND: Self::Balance;
+	/// The number of blocks between payouts of the approved proposals.
+	const SPEND_PERIOD: Self::BlockNumber;
+	/// The maximum number of approved proposals waiting to be paid out.
+	const MAX_APPROVALS: usize;
+}
+
+/// The index of a spend proposal.
+pub type ProposalIndex = u32;
+
+/// A proposal to spend funds from the treasury.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal<AccountID, Balance> {
+	/// The account which made the proposal.
+	pub proposer: AccountID,
+	/// The amount to pay out.
+	pub value: Balance,
+	/// The account to pay out to.
+	pub beneficiary: AccountID,
+	/// The amount reserved from the proposer.
+	pub bond: Balance,
+}
+
+/// The proposal type used by a runtime `T`.
+pub type ProposalOf<T> =
+	Proposal<<T as system::Config>::AccountID, <T as balances::Config>::Balance>;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<AccountID, Balance> {
+	/// A new spend proposal was made.
+	Proposed { proposal_index: ProposalIndex },
+	/// A spend proposal was rejected, and the bond of the proposer was slashed.
+	Rejected { proposal_index: ProposalIndex, slashed: Balance },
+	/// The approved proposals are being paid out, with `budget_remaining` left in the pot.
+	Spending { budget_remaining: Balance },
+	/// A spend proposal was paid out.
+	Awarded { proposal_index: ProposalIndex, award: Balance, account: AccountID },
+}
+
+/// This is the Treasury Module.
+/// It owns a pot of funds, filled by the fees and slashes of the `balances` pallet, which accounts
+/// can propose to spend. Approved proposals are paid out every `SPEND_PERIOD` blocks.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// The number of proposals which have been made, used as the index of the next proposal.
+	proposal_count: ProposalIndex,
+	/// A map from a proposal index to the proposal, until it is paid out or rejected.
+	proposals: BTreeMap<ProposalIndex, ProposalOf<T>>,
+	/// The approved proposals, in the order they will be paid out.
+	approvals: Vec<ProposalIndex>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Treasury Module.
+	pub fn new() -> Self {
+		Self { proposal_count: 0, proposals: BTreeMap::new(), approvals: Vec::new() }
+	}
+
+	/// Get the account which holds the funds of the treasury.
+	pub fn account_id() -> T::AccountID {
+		T::AccountID::derive_account(&"treasury")
+	}
+
+	/// Get the funds in the treasury pot.
+	pub fn pot(runtime: &T) -> T::Balance {
+		GetPallet::<balances::Pallet<T>>::pallet(runtime).usable_balance(&Self::account_id())
+	}
+
+	/// Get a spend proposal.
+	pub fn proposal(&self, index: ProposalIndex) -> Option<&ProposalOf<T>> {
+		self.proposals.get(&index)
+	}
+
+	/// Get the approved proposals, in the order they will be paid out.
+	pub fn approvals(&self) -> &[ProposalIndex] {
+		&self.approvals
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event<T::AccountID, T::Balance>) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+
+	/// Pay out the approved proposals, in order, while the pot has enough funds for them. The rest
+	/// stay approved until the next payout.
+	fn spend_funds(runtime: &mut T) {
+		let pot = Self::account_id();
+		let mut budget_remaining = Self::pot(runtime);
+		Self::deposit_event(runtime, Event::Spending { budget_remaining });
+
+		let treasury = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let approvals = core::mem::take(&mut treasury.approvals);
+		let mut missed = Vec::new();
+		for proposal_index in approvals {
+			let treasury = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+			let proposal = match treasury.proposals.get(&proposal_index) {
+				Some(proposal) if proposal.value <= budget_remaining => proposal.clone(),
+				Some(_) => {
+					missed.push(proposal_index);
+					continue;
+				},
+				None => continue,
+			};
+
+			let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+			let beneficiary = proposal.beneficiary.clone();
+			if balances.do_transfer(pot.clone(), beneficiary, proposal.value).is_err() {
+				missed.push(proposal_index);
+				continue;
+			}
+			balances.unreserve(&proposal.proposer, proposal.bond);
+			budget_remaining =
+				budget_remaining.checked_sub(&proposal.value).unwrap_or(T::Balance::zero());
+
+			GetPallet::<Pallet<T>>::pallet_mut(runtime).proposals.remove(&proposal_index);
+			let event = Event::Awarded {
+				proposal_index,
+				award: proposal.value,
+				account: proposal.beneficiary,
+			};
+			Self::deposit_event(runtime, event);
+		}
+		GetPallet::<Pallet<T>>::pallet_mut(runtime).approvals = missed;
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {
+	/// Pay out the approved proposals every `SPEND_PERIOD` blocks.
+	fn on_initialize(runtime: &mut T, n: T::BlockNumber) -> DispatchResult {
+		if n.checked_rem(&T::SPEND_PERIOD).is_some_and(|rem| rem.is_zero()) {
+			Self::spend_funds(runtime);
+		}
+		Ok(())
+	}
+}
+
+/// Funds which are burned or slashed by the `balances` pallet go to the treasury pot.
+impl<T: Config> balances::OnUnbalanced<T> for Pallet<T> {
+	fn on_unbalanced(balances: &mut balances::Pallet<T>, amount: T::Balance) {
+		// If the pot would overflow, the funds are simply destroyed.
+		let _ = balances.deposit(&Self::account_id(), amount);
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Propose to spend `value` from the treasury, paying out to the `beneficiary`.
+	/// The `PROPOSAL_BOND` is reserved from the `caller` until the proposal is paid out or
+	/// rejected.
+	pub fn propose_spend(
+		runtime: &mut T,
+		caller: T::AccountID,
+		value: T::Balance,
+		beneficiary: T::AccountID,
+	) -> DispatchResult {
+		let bond = T::PROPOSAL_BOND;
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).reserve(&caller, bond)?;
+
+		let treasury = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let proposal_index = treasury.proposal_count;
+		treasury.proposal_count = proposal_index.checked_add(1).ok_or("Overflow error")?;
+		let proposal = Proposal { proposer: caller, value, beneficiary, bond };
+		treasury.proposals.insert(proposal_index, proposal);
+
+		Self::deposit_event(runtime, Event::Proposed { proposal_index });
+		Ok(())
+	}
+
+	/// Reject the proposal at `proposal_index`, slashing the bond of the proposer.
+	/// Only the `ApproveOrigin` can reject proposals.
+	pub fn reject_proposal(
+		runtime: &mut T,
+		caller: T::AccountID,
+		proposal_index: ProposalIndex,
+	) -> DispatchResult {
+		T::ApproveOrigin::ensure_origin(runtime, &caller)?;
+		let treasury = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let proposal = treasury.proposals.remove(&proposal_index).ok_or("Proposal not found")?;
+		treasury.approvals.retain(|index| *index != proposal_index);
+
+		let balances = GetPallet::<balances::Pallet<T>>::pallet_mut(runtime);
+		let slashed = balances.slash_reserved(&proposal.proposer, proposal.bond);
+		Self::deposit_event(runtime, Event::Rejected { proposal_index, slashed });
+		Ok(())
+	}
+
+	/// Approve the proposal at `proposal_index`, so that it is paid out at the next payout.
+	/// Only the `ApproveOrigin` can approve proposals.
+	pub fn approve_proposal(
+		runtime: &mut T,
+		caller: T::AccountID,
+		proposal_index: ProposalIndex,
+	) -> DispatchResult {
+		T::ApproveOrigin::ensure_origin(runtime, &caller)?;
+		let treasury = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		if !treasury.proposals.contains_key(&proposal_index) {
+			return Err("Proposal not found");
+		}
+		if treasury.approvals.contains(&proposal_index) {
+			return Err("Proposal already approved");
+		}
+		if treasury.approvals.len() >= T::MAX_APPROVALS {
+			return Err("Too many approvals");
+		}
+		treasury.approvals.push(proposal_index);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{new_test_runtime, run_to_block, support, Runtime, RuntimeEvent};
+
+	type Treasury = super::Pallet<Runtime>;
+
+	#[test]
+	fn slashes_and_burns_fill_the_pot() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let alice = "alice".to_string();
+
+		assert_eq!(runtime.balances.burn(&alice, 10), Ok(()));
+		assert_eq!(Treasury::pot(&runtime), 110);
+		assert_eq!(runtime.balances.reserve(&alice, 20), Ok(()));
+		assert_eq!(runtime.balances.slash_reserved(&alice, 20), 20);
+		assert_eq!(Treasury::pot(&runtime), 130);
+		assert_eq!(runtime.balances.balance(&alice), 70);
+	}
+
+	#[test]
+	fn reject_proposal_slashes_bond() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let alice = "alice".to_string();
+		let root = support::root_account::<String>();
+
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 50, "bob".into()), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+
+		assert_eq!(Treasury::reject_proposal(&mut runtime, alice.clone(), 0), Err("Bad origin"));
+		assert_eq!(Treasury::reject_proposal(&mut runtime, root.clone(), 0), Ok(()));
+		assert_eq!(Treasury::reject_proposal(&mut runtime, root, 0), Err("Proposal not found"));
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+		assert_eq!(Treasury::pot(&runtime), 110);
+		assert_eq!(
+			runtime.system.events(),
+			&[
+				RuntimeEvent::treasury(Event::Proposed { proposal_index: 0 }),
+				RuntimeEvent::treasury(Event::Rejected { proposal_index: 0, slashed: 10 }),
+			]
+		);
+	}
+
+	#[test]
+	fn approved_proposals_are_paid_out() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		runtime.balances.set_balance(&Treasury::account_id(), 100);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let root = support::root_account::<String>();
+
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 60, bob.clone()), Ok(()));
+		assert_eq!(Treasury::propose_spend(&mut runtime, alice.clone(), 50, bob.clone()), Ok(()));
+		assert_eq!(Treasury::approve_proposal(&mut runtime, alice.clone(), 0), Err("Bad origin"));
+		assert_eq!(Treasury::approve_proposal(&mut runtime, root.clone(), 0), Ok(()));
+		assert_eq!(
+			Treasury::approve_proposal(&mut runtime, root.clone(), 0),
+			Err("Proposal already approved")
+		);
+		assert_eq!(Treasury::approve_proposal(&mut runtime, root, 1), Ok(()));
+
+		// The pot can only afford the first proposal, so the second one waits for more funds.
+		run_to_block(&mut runtime, 10);
+		assert_eq!(runtime.balances.balance(&bob), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 10);
+		assert_eq!(runtime.balances.balance(&alice), 90);
+		assert_eq!(Treasury::pot(&runtime), 40);
+		assert!(runtime.treasury.proposal(0).is_none());
+		assert_eq!(runtime.treasury.approvals(), &[1]);
+		assert_eq!(
+			&runtime.system.events()[..2],
+			&[
+				RuntimeEvent::treasury(Event::Spending { budget_remaining: 100 }),
+				RuntimeEvent::treasury(Event::Awarded {
+					proposal_index: 0,
+					award: 60,
+					account: bob.clone()
+				}),
+			]
+		);
+
+		runtime.balances.set_balance(&Treasury::account_id(), 50);
+		run_to_block(&mut runtime, 20);
+		assert_eq!(runtime.balances.balance(&bob), 110);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert!(runtime.treasury.approvals().is_empty());
+	}
+}
@@ -0,0 +1,181 @@
+use crate::{
+	support::{self, DeriveAccount, Dispatch, DispatchResult, GetPallet},
+	system,
+};
+use core::{hash::Hash, marker::PhantomData};
+
+pub trait Config:
+	system::Config<AccountID: DeriveAccount + Hash, RuntimeEvent: From<Event>>
+	+ Sized
+	+ Clone
+	+ Dispatch<Caller = Self::AccountID, Call = Self::RuntimeCall>
+	+ GetPallet<system::Pallet<Self>>
+{
+	/// The overarching call type, which can be batched.
+	type RuntimeCall;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+	/// A call in a batch failed with `error`. The rest of the batch was still dispatched.
+	ItemFailed { index: u32, error: &'static str },
+	/// A batch was dispatched, and all of its calls succeeded.
+	BatchCompleted,
+	/// A batch was dispatched, but some of its calls failed.
+	BatchCompletedWithErrors,
+}
+
+/// This is the Utility Module.
+/// It has no state of its own, and lets accounts dispatch several calls at once, or dispatch calls
+/// from derivative accounts.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	_marker: PhantomData<T>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Utility Module.
+	pub fn new() -> Self {
+		Self { _marker: PhantomData }
+	}
+
+	/// Derive the account with the given `index` which is controlled by `who`.
+	pub fn derivative_account_id(who: &T::AccountID, index: u16) -> T::AccountID {
+		T::AccountID::derive_account(&("utility", who, index))
+	}
+
+	fn deposit_event(runtime: &mut T, event: Event) {
+		GetPallet::<system::Pallet<T>>::pallet_mut(runtime).deposit_event(event);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Dispatch each of the `calls` on behalf of the `caller`.
+	/// A call which fails does not stop the rest of the batch. Instead, its index is reported with
+	/// an `ItemFailed` event.
+	pub fn batch(
+		runtime: &mut T,
+		caller: T::AccountID,
+		calls: Vec<T::RuntimeCall>,
+	) -> DispatchResult {
+		let mut failed = false;
+		for (index, call) in calls.into_iter().enumerate() {
+			if let Err(error) = runtime.dispatch(caller.clone(), call) {
+				failed = true;
+				Self::deposit_event(runtime, Event::ItemFailed { index: index as u32, error });
+			}
+		}
+		let event = if failed { Event::BatchCompletedWithErrors } else { Event::BatchCompleted };
+		Self::deposit_event(runtime, event);
+		Ok(())
+	}
+
+	/// Dispatch each of the `calls` on behalf of the `caller`, atomically.
+	/// If any call fails, the changes made by the whole batch are reverted, and the error is
+	/// returned.
+	pub fn batch_all(
+		runtime: &mut T,
+		caller: T::AccountID,
+		calls: Vec<T::RuntimeCall>,
+	) -> DispatchResult {
+		support::with_transaction(runtime, |runtime| {
+			for call in calls {
+				runtime.dispatch(caller.clone(), call)?;
+			}
+			Self::deposit_event(runtime, Event::BatchCompleted);
+			Ok(())
+		})
+	}
+
+	/// Dispatch `call` from the derivative account of the `caller` with the given `index`.
+	/// This lets an account separate its funds into several accounts, which it still controls.
+	pub fn as_derivative(
+		runtime: &mut T,
+		caller: T::AccountID,
+		index: u16,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult {
+		let derivative = Self::derivative_account_id(&caller, index);
+		runtime.dispatch(derivative, *call)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Event;
+	use crate::{balances, new_test_runtime, Runtime, RuntimeCall, RuntimeEvent};
+
+	type Utility = super::Pallet<Runtime>;
+
+	fn transfer(to: &str, amount: u128) -> RuntimeCall {
+		RuntimeCall::balances(balances::Call::transfer { to: to.to_string().into(), amount })
+	}
+
+	#[test]
+	fn batch_continues_after_failure() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let calls = vec![transfer("bob", 30), transfer("bob", 500), transfer("charlie", 20)];
+
+		assert_eq!(Utility::batch(&mut runtime, "alice".into(), calls), Ok(()));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 50);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 30);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 20);
+		assert_eq!(
+			runtime.system.events(),
+			&[
+				RuntimeEvent::utility(Event::ItemFailed { index: 1, error: "Not enough funds" }),
+				RuntimeEvent::utility(Event::BatchCompletedWithErrors),
+			]
+		);
+	}
+
+	#[test]
+	fn batch_all_is_atomic() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let calls = vec![transfer("bob", 30), transfer("bob", 500), transfer("charlie", 20)];
+
+		let result = Utility::batch_all(&mut runtime, "alice".into(), calls);
+		assert_eq!(result, Err("Not enough funds"));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 100);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 0);
+		assert!(runtime.system.events().is_empty());
+
+		let calls = vec![transfer("bob", 30), transfer("charlie", 20)];
+		assert_eq!(Utility::batch_all(&mut runtime, "alice".into(), calls), Ok(()));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 50);
+		assert_eq!(runtime.system.events(), &[RuntimeEvent::utility(Event::BatchCompleted)]);
+	}
+
+	#[test]
+	fn nested_batches() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let inner = vec![transfer("bob", 10), transfer("charlie", 10)];
+		let calls = vec![
+			RuntimeCall::utility(super::Call::batch_all { calls: inner }),
+			transfer("bob", 10),
+		];
+
+		assert_eq!(Utility::batch_all(&mut runtime, "alice".into(), calls), Ok(()));
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 70);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 20);
+	}
+
+	#[test]
+	fn as_derivative() {
+		let mut runtime = new_test_runtime(&[("alice", 100)]);
+		let derivative = Utility::derivative_account_id(&"alice".to_string(), 0);
+		assert_ne!(derivative, Utility::derivative_account_id(&"alice".to_string(), 1));
+		assert_ne!(derivative, Utility::derivative_account_id(&"bob".to_string(), 0));
+
+		runtime.balances.set_balance(&derivative, 40);
+		let call = Box::new(transfer("bob", 40));
+		assert_eq!(Utility::as_derivative(&mut runtime, "alice".into(), 0, call), Ok(()));
+		assert_eq!(runtime.balances.balance(&derivative), 0);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 40);
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 100);
+	}
+}
@@ -0,0 +1,340 @@
+use crate::{
+	balances::{self, LockIdentifier},
+	support::{self, Convert, DispatchResult, GetPallet},
+	system,
+};
+use num::{
+	traits::{Bounded, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub},
+	One, Zero,
+};
+use std::collections::BTreeMap;
+
+/// The identifier of the lock this pallet places on vesting accounts.
+const VESTING_ID: LockIdentifier = "vesting";
+
+pub trait Config:
+	balances::Config<Balance: One + CheckedMul + CheckedDiv + Bounded>
+	+ Sized
+	+ GetPallet<system::Pallet<Self>>
+	+ GetPallet<balances::Pallet<Self>>
+	+ GetPallet<Pallet<Self>>
+{
+	/// Converts a block number into a balance, so that the amount unlocked by a schedule can be
+	/// calculated from the number of blocks which have passed.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, Self::Balance>;
+	/// The maximum number of vesting schedules an account can have at the same time.
+	const MAX_VESTING_SCHEDULES: usize;
+}
+
+/// A vesting schedule, which linearly unlocks `locked` over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VestingInfo<Balance, BlockNumber> {
+	/// The amount locked at the start of the schedule.
+	pub locked: Balance,
+	/// The amount which unlocks every block after `starting_block`.
+	pub per_block: Balance,
+	/// The block at which the funds start to unlock.
+	pub starting_block: BlockNumber,
+}
+
+/// The vesting schedule type used by a runtime `T`.
+pub type VestingInfoOf<T> =
+	VestingInfo<<T as balances::Config>::Balance, <T as system::Config>::BlockNumber>;
+
+impl<Balance, BlockNumber> VestingInfo<Balance, BlockNumber>
+where
+	Balance: Zero + One + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + Copy + Ord,
+	BlockNumber: CheckedSub + Copy,
+{
+	/// Check the schedule is valid: it should lock some funds, and unlock some funds every block.
+	fn validate(&self) -> DispatchResult {
+		if self.locked.is_zero() {
+			return Err("Vesting schedule locks nothing");
+		}
+		if self.per_block.is_zero() {
+			return Err("Vesting schedule never unlocks");
+		}
+		Ok(())
+	}
+
+	/// Get the amount which is still locked by this schedule at block `n`.
+	pub fn locked_at<C: Convert<BlockNumber, Balance>>(&self, n: BlockNumber) -> Balance {
+		let elapsed = match n.checked_sub(&self.starting_block) {
+			Some(elapsed) => C::convert(elapsed),
+			// The schedule has not started yet.
+			None => return self.locked,
+		};
+		// If the multiplication overflows, everything has surely been unlocked.
+		let unlocked = self.per_block.checked_mul(&elapsed).unwrap_or(self.locked);
+		self.locked.checked_sub(&unlocked).unwrap_or(Balance::zero())
+	}
+
+	/// Get the block at which this schedule has unlocked all of its funds, as a balance.
+	fn ending_block_as_balance<C: Convert<BlockNumber, Balance>>(&self) -> Option<Balance> {
+		let duration = checked_div_ceil(self.locked, self.per_block)?;
+		C::convert(self.starting_block).checked_add(&duration)
+	}
+}
+
+/// Divide `a` by `b`, rounding up.
+fn checked_div_ceil<Balance>(a: Balance, b: Balance) -> Option<Balance>
+where
+	Balance: One + CheckedAdd + CheckedMul + CheckedDiv + Copy + Ord,
+{
+	let quotient = a.checked_div(&b)?;
+	if quotient.checked_mul(&b)? < a {
+		return quotient.checked_add(&Balance::one());
+	}
+	Some(quotient)
+}
+
+/// This is the Vesting Module.
+/// It locks funds of an account under one or more schedules, which unlock linearly as blocks pass.
+/// The locks are enforced by the Balances Module.
+#[derive(Debug, Clone)]
+pub struct Pallet<T: Config> {
+	/// A map from an account to its vesting schedules.
+	vesting: BTreeMap<T::AccountID, Vec<VestingInfoOf<T>>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Vesting Module.
+	pub fn new() -> Self {
+		Self { vesting: BTreeMap::new() }
+	}
+
+	/// Get the vesting schedules of an account.
+	pub fn schedules(&self, who: &T::AccountID) -> &[VestingInfoOf<T>] {
+		self.vesting.get(who).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	/// Get the amount of the balance of `who` which is still locked by vesting at block `n`.
+	/// If the total overflows, everything is locked.
+	pub fn vesting_balance(&self, who: &T::AccountID, n: T::BlockNumber) -> T::Balance {
+		self.schedules(who)
+			.iter()
+			.map(|schedule| schedule.locked_at::<T::BlockNumberToBalance>(n))
+			.fold(T::Balance::zero(), |total, locked| {
+				total.checked_add(&locked).unwrap_or_else(T::Balance::max_value)
+			})
+	}
+
+	/// Drop the finished schedules of `who`, and update the lock on its balance to match the funds
+	/// which are still vesting.
+	fn update_lock(runtime: &mut T, who: &T::AccountID) {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let vesting = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+
+		if let Some(schedules) = vesting.vesting.get_mut(who) {
+			schedules
+				.retain(|schedule| !schedule.locked_at::<T::BlockNumberToBalance>(now).is_zero());
+			if schedules.is_empty() {
+				vesting.vesting.remove(who);
+			}
+		}
+
+		let locked = vesting.vesting_balance(who, now);
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).set_lock(VESTING_ID, who, locked);
+	}
+}
+
+impl<T: Config> support::Hooks<T, T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Unlock the funds of the `caller` which have vested so far.
+	/// This function will return an error if the caller has no vesting schedules.
+	pub fn vest(runtime: &mut T, caller: T::AccountID) -> DispatchResult {
+		if GetPallet::<Pallet<T>>::pallet(runtime).schedules(&caller).is_empty() {
+			return Err("Account is not vesting");
+		}
+		Self::update_lock(runtime, &caller);
+		Ok(())
+	}
+
+	/// Transfer `schedule.locked` from the `caller` to `target`, locked under the given `schedule`.
+	/// This function will return an error if the schedule is invalid, if `target` already has the
+	/// maximum number of schedules, or if the transfer fails.
+	pub fn vested_transfer(
+		runtime: &mut T,
+		caller: T::AccountID,
+		target: T::AccountID,
+		schedule: VestingInfoOf<T>,
+	) -> DispatchResult {
+		schedule.validate()?;
+		if GetPallet::<Pallet<T>>::pallet(runtime).schedules(&target).len() >=
+			T::MAX_VESTING_SCHEDULES
+		{
+			return Err("Too many vesting schedules");
+		}
+
+		GetPallet::<balances::Pallet<T>>::pallet_mut(runtime).do_transfer(
+			caller,
+			target.clone(),
+			schedule.locked,
+		)?;
+
+		let vesting = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		vesting.vesting.entry(target.clone()).or_default().push(schedule);
+		Self::update_lock(runtime, &target);
+		Ok(())
+	}
+
+	/// Merge two vesting schedules of the `caller` into a single schedule.
+	/// The new schedule starts now, locks what is still locked by both schedules, and ends no later
+	/// than the later of the two would have ended. Merging a schedule with itself does nothing.
+	pub fn merge_schedules(
+		runtime: &mut T,
+		caller: T::AccountID,
+		schedule1_index: u32,
+		schedule2_index: u32,
+	) -> DispatchResult {
+		let now = GetPallet::<system::Pallet<T>>::pallet(runtime).block_number();
+		let vesting = GetPallet::<Pallet<T>>::pallet_mut(runtime);
+		let schedules = vesting.vesting.get_mut(&caller).ok_or("Account is not vesting")?;
+
+		let (index1, index2) = (schedule1_index as usize, schedule2_index as usize);
+		if index1 >= schedules.len() || index2 >= schedules.len() {
+			return Err("Vesting schedule index out of bounds");
+		}
+		if index1 == index2 {
+			return Ok(());
+		}
+		// Remove the later index first, so the earlier index stays valid.
+		let schedule2 = schedules.remove(index1.max(index2));
+		let schedule1 = schedules.remove(index1.min(index2));
+
+		let locked = schedule1
+			.locked_at::<T::BlockNumberToBalance>(now)
+			.checked_add(&schedule2.locked_at::<T::BlockNumberToBalance>(now))
+			.ok_or("Balance overflow error")?;
+		// Finished schedules are simply dropped, since there is nothing left to merge.
+		if !locked.is_zero() {
+			let starting_block = now.max(schedule1.starting_block).max(schedule2.starting_block);
+			let ending_block = schedule1
+				.ending_block_as_balance::<T::BlockNumberToBalance>()
+				.max(schedule2.ending_block_as_balance::<T::BlockNumberToBalance>())
+				.ok_or("Balance overflow error")?;
+			let duration = ending_block
+				.checked_sub(&T::BlockNumberToBalance::convert(starting_block))
+				.filter(|duration| !duration.is_zero())
+				.unwrap_or(T::Balance::one());
+			// Round up, so that the merged schedule does not end later than the later of the two.
+			let per_block = checked_div_ceil(locked, duration).ok_or("Balance overflow error")?;
+			schedules.push(VestingInfo { locked, per_block, starting_block });
+		}
+
+		Self::update_lock(runtime, &caller);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Config, VestingInfo};
+	use crate::{new_test_runtime, run_to_block, Runtime};
+
+	type Vesting = super::Pallet<Runtime>;
+
+	#[test]
+	fn vested_transfer_unlocks_linearly() {
+		let mut runtime = new_test_runtime(&[("alice", 1000)]);
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let schedule = VestingInfo { locked: 100, per_block: 10, starting_block: 5 };
+
+		assert_eq!(Vesting::vested_transfer(&mut runtime, alice, bob.clone(), schedule), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.balances.locked(&bob), 100);
+		let result = runtime.balances.do_transfer(bob.clone(), charlie.clone(), 1);
+		assert_eq!(result, Err("Balance is locked"));
+
+		// Nothing unlocks before the starting block.
+		run_to_block(&mut runtime, 5);
+		assert_eq!(Vesting::vest(&mut runtime, bob.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&bob), 100);
+
+		run_to_block(&mut runtime, 8);
+		assert_eq!(Vesting::vest(&mut runtime, bob.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&bob), 70);
+		assert_eq!(runtime.balances.do_transfer(bob.clone(), charlie.clone(), 30), Ok(()));
+		let result = runtime.balances.do_transfer(bob.clone(), charlie, 1);
+		assert_eq!(result, Err("Balance is locked"));
+
+		// Once everything is vested, the schedule and the lock are removed.
+		run_to_block(&mut runtime, 15);
+		assert_eq!(Vesting::vest(&mut runtime, bob.clone()), Ok(()));
+		assert_eq!(runtime.balances.locked(&bob), 0);
+		assert!(runtime.vesting.schedules(&bob).is_empty());
+		assert_eq!(Vesting::vest(&mut runtime, bob), Err("Account is not vesting"));
+	}
+
+	#[test]
+	fn vested_transfer_checks_schedule() {
+		let mut runtime = new_test_runtime(&[("alice", 1000)]);
+		let bob = "bob".to_string();
+		let schedule = VestingInfo { locked: 10, per_block: 1, starting_block: 0 };
+
+		let never_unlocks = VestingInfo { per_block: 0, ..schedule };
+		let result =
+			Vesting::vested_transfer(&mut runtime, "alice".into(), bob.clone(), never_unlocks);
+		assert_eq!(result, Err("Vesting schedule never unlocks"));
+
+		let too_much = VestingInfo { locked: 2000, ..schedule };
+		assert_eq!(
+			Vesting::vested_transfer(&mut runtime, "alice".into(), bob.clone(), too_much),
+			Err("Not enough funds")
+		);
+		assert!(runtime.vesting.schedules(&bob).is_empty());
+
+		for _ in 0..<Runtime as Config>::MAX_VESTING_SCHEDULES {
+			assert_eq!(
+				Vesting::vested_transfer(&mut runtime, "alice".into(), bob.clone(), schedule),
+				Ok(())
+			);
+		}
+		assert_eq!(
+			Vesting::vested_transfer(&mut runtime, "alice".into(), bob.clone(), schedule),
+			Err("Too many vesting schedules")
+		);
+	}
+
+	#[test]
+	fn merge_schedules() {
+		let mut runtime = new_test_runtime(&[("alice", 1000)]);
+		let bob = "bob".to_string();
+		let schedule1 = VestingInfo { locked: 100, per_block: 10, starting_block: 0 };
+		let schedule2 = VestingInfo { locked: 60, per_block: 2, starting_block: 10 };
+
+		for schedule in [schedule1, schedule2] {
+			let alice = "alice".to_string();
+			let result = Vesting::vested_transfer(&mut runtime, alice, bob.clone(), schedule);
+			assert_eq!(result, Ok(()));
+		}
+		assert_eq!(runtime.balances.locked(&bob), 160);
+
+		run_to_block(&mut runtime, 5);
+		assert_eq!(
+			Vesting::merge_schedules(&mut runtime, bob.clone(), 0, 2),
+			Err("Vesting schedule index out of bounds")
+		);
+		assert_eq!(
+			Vesting::merge_schedules(&mut runtime, bob.clone(), 2, 2),
+			Err("Vesting schedule index out of bounds")
+		);
+		let result = Vesting::merge_schedules(&mut runtime, "charlie".into(), 0, 0);
+		assert_eq!(result, Err("Account is not vesting"));
+		assert_eq!(Vesting::merge_schedules(&mut runtime, bob.clone(), 0, 0), Ok(()));
+		assert_eq!(runtime.vesting.schedules(&bob).len(), 2);
+		assert_eq!(Vesting::merge_schedules(&mut runtime, bob.clone(), 1, 0), Ok(()));
+
+		// 50 is left from the first schedule, and all 60 from the second, which ends at block 40.
+		// The merged schedule unlocks 4 per block from block 10, so it ends at block 38.
+		assert_eq!(
+			runtime.vesting.schedules(&bob),
+			&[VestingInfo { locked: 110, per_block: 4, starting_block: 10 }]
+		);
+		assert_eq!(runtime.balances.locked(&bob), 110);
+	}
+}